@@ -0,0 +1,92 @@
+use crate::level::point::Point;
+
+// the rectangular part of the level, leaves of the tree contain rooms
+#[derive(Debug, Clone)]
+pub struct BspNode {
+    position: Point,
+    height: usize,
+    width: usize,
+    parent: Option<usize>,
+    children: Option<(usize, usize)>
+}
+
+impl BspNode {
+    pub fn position(&self) -> &Point {
+        return &self.position;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn parent(&self) -> Option<usize> {
+        return self.parent;
+    }
+
+    pub fn children(&self) -> Option<(usize, usize)> {
+        return self.children;
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        return self.children.is_none();
+    }
+}
+
+// binary space partition of the level, the first node is the root
+// children are always added after the parent, so nodes in the reversed order are visited from leaves to the root
+#[derive(Debug, Clone)]
+pub struct BspTree {
+    nodes: Vec<BspNode>
+}
+
+impl BspTree {
+    pub fn new(position: Point, height: usize, width: usize) -> BspTree {
+        return BspTree { nodes: vec![BspNode { position, height, width, parent: None, children: None }] };
+    }
+
+    // split the leaf into two parts, if is_horizontal, then the first part contains offset rows, otherwise offset columns
+    pub fn split(&mut self, index: usize, is_horizontal: bool, offset: usize) -> (usize, usize) {
+        let node = self.nodes[index].clone();
+        let (first, second) = if is_horizontal {
+            (BspNode { position: node.position.clone(), height: offset, width: node.width, parent: Some(index), children: None },
+             BspNode { position: Point::new(node.position.x() + offset as i32, node.position.y()), height: node.height - offset, width: node.width, parent: Some(index), children: None })
+        }
+        else {
+            (BspNode { position: node.position.clone(), height: node.height, width: offset, parent: Some(index), children: None },
+             BspNode { position: Point::new(node.position.x(), node.position.y() + offset as i32), height: node.height, width: node.width - offset, parent: Some(index), children: None })
+        };
+        self.nodes.push(first);
+        self.nodes.push(second);
+        let children = (self.nodes.len() - 2, self.nodes.len() - 1);
+        self.nodes[index].children = Some(children);
+        return children;
+    }
+
+    pub fn nodes(&self) -> &Vec<BspNode> {
+        return &self.nodes;
+    }
+
+    pub fn leaves(&self) -> Vec<usize> {
+        return (0..self.nodes.len()).filter(|&i| self.nodes[i].is_leaf()).collect();
+    }
+
+    // all leaves in the subtree of the node
+    pub fn subtree_leaves(&self, index: usize) -> Vec<usize> {
+        let mut leaves: Vec<usize> = Vec::new();
+        let mut stack: Vec<usize> = vec![index];
+        while let Some(current) = stack.pop() {
+            match self.nodes[current].children {
+                Some((first, second)) => {
+                    stack.push(second);
+                    stack.push(first);
+                },
+                None => leaves.push(current)
+            }
+        }
+        return leaves;
+    }
+}
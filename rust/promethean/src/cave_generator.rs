@@ -0,0 +1,177 @@
+use super::preudo_random::PseudoRandom;
+use crate::level::options::{CaveSettings, CavernMethod};
+
+// floor mask of the cave, x is the row, y is the column
+// cells inside the level border are always walls, cells outside the level are counted as walls
+pub fn generate_cave(random: &mut PseudoRandom, height: usize, width: usize, border: usize, settings: &CaveSettings) -> Vec<Vec<bool>> {
+    let is_inside = |x: usize, y: usize| -> bool {
+        return x >= border && y >= border && x + border < height && y + border < width;
+    };
+
+    let mut walls: Vec<Vec<bool>> = vec![vec![true; width]; height];
+    for x in 0..height {
+        for y in 0..width {
+            if is_inside(x, y) {
+                walls[x][y] = random.next_float() < settings.fill_ratio();
+            }
+        }
+    }
+
+    for _ in 0..settings.iterations() {
+        let mut next_walls: Vec<Vec<bool>> = vec![vec![true; width]; height];
+        for x in 0..height {
+            for y in 0..width {
+                if is_inside(x, y) {
+                    next_walls[x][y] = settings.is_wall(walls[x][y], wall_neighbours(&walls, x, y));
+                }
+            }
+        }
+        walls = next_walls;
+    }
+
+    let mut floor: Vec<Vec<bool>> = walls.iter().map(|row| row.iter().map(|w| !w).collect()).collect();
+    separate_diagonal_floor(&mut floor);
+    for region in floor_regions(&floor) {
+        if region.len() < settings.min_region_size() {
+            for (x, y) in region {
+                floor[x][y] = false;
+            }
+        }
+    }
+    return floor;
+}
+
+// the part of the cavern bounding box, which is covered by the drunkard's walk
+const CAVERN_COVERAGE: f64 = 0.5;
+
+// floor of the cavern inside the bounding box of the room, the floor is connected and contains the center of the box
+pub fn generate_cavern(random: &mut PseudoRandom, height: usize, width: usize, settings: &CaveSettings, method: CavernMethod) -> Vec<Vec<bool>> {
+    let center = (height / 2, width / 2);
+    if method == CavernMethod::CellularAutomata {
+        // the box is surrounded by one virtual wall, so the automaton does not grow the floor to the room border
+        // only the largest region is used, so small regions are not removed by the automaton
+        let cavern_settings = CaveSettings::new(settings.fill_ratio(), settings.birth(), settings.survival(), settings.iterations(), 0);
+        let mut floor = generate_cave(random, height + 2, width + 2, 1, &cavern_settings);
+        floor = floor[1..height + 1].iter().map(|row| row[1..width + 1].to_vec()).collect();
+        let regions = floor_regions(&floor);
+        if let Some(largest) = regions.iter().max_by_key(|r| r.len()) {
+            let mut cavern: Vec<Vec<bool>> = vec![vec![false; width]; height];
+            for &(x, y) in largest {
+                cavern[x][y] = true;
+            }
+            connect_to_center(&mut cavern, largest, center);
+            separate_diagonal_floor(&mut cavern);
+            return cavern;
+        };
+    }
+    let mut cavern = drunkard_walk(random, height, width, center, CAVERN_COVERAGE);
+    separate_diagonal_floor(&mut cavern);
+    return cavern;
+}
+
+// carve the straight path from the center to the closest cell of the region
+fn connect_to_center(floor: &mut [Vec<bool>], region: &[(usize, usize)], center: (usize, usize)) {
+    if floor[center.0][center.1] {
+        return;
+    }
+    let closest = region.iter().min_by_key(|&&(x, y)| x.abs_diff(center.0) + y.abs_diff(center.1)).copied().unwrap_or(center);
+    for x in closest.0.min(center.0)..=closest.0.max(center.0) {
+        floor[x][center.1] = true;
+    }
+    for y in closest.1.min(center.1)..=closest.1.max(center.1) {
+        floor[closest.0][y] = true;
+    }
+}
+
+// random walk from the start cell in four directions until the given part of the area becomes the floor
+pub fn drunkard_walk(random: &mut PseudoRandom, height: usize, width: usize, start: (usize, usize), coverage: f64) -> Vec<Vec<bool>> {
+    let mut floor: Vec<Vec<bool>> = vec![vec![false; width]; height];
+    let target = ((height * width) as f64 * coverage).ceil().max(1.0) as usize;
+    let (mut x, mut y) = start;
+    floor[x][y] = true;
+    let mut covered: usize = 1;
+    // the limit of steps for the case, when the coverage is too large
+    let mut steps = 100 * height * width;
+    while covered < target && steps > 0 {
+        steps -= 1;
+        match random.next(0, 3) {
+            0 if x > 0 => x -= 1,
+            1 if x + 1 < height => x += 1,
+            2 if y > 0 => y -= 1,
+            3 if y + 1 < width => y += 1,
+            _ => continue
+        };
+        if !floor[x][y] {
+            floor[x][y] = true;
+            covered += 1;
+        }
+    }
+    return floor;
+}
+
+fn wall_neighbours(walls: &[Vec<bool>], x: usize, y: usize) -> usize {
+    let mut count: usize = 0;
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+            let nx = x as i32 + dx;
+            let ny = y as i32 + dy;
+            if nx < 0 || ny < 0 || nx as usize >= walls.len() || ny as usize >= walls[0].len() || walls[nx as usize][ny as usize] {
+                count += 1;
+            }
+        }
+    }
+    return count;
+}
+
+// wall patterns do not support floor cells, which touch each other only by the corner
+// so one of two walls between such cells becomes the floor
+// all cells are checked, because the floor of caverns reaches edges of the room box
+// caves, tunnels and other masks with the level border have walls on edges, so edges do not change them
+pub fn separate_diagonal_floor(floor: &mut [Vec<bool>]) {
+    let height = floor.len();
+    let width = if height > 0 { floor[0].len() } else { 0 };
+    for x in 0..height.saturating_sub(1) {
+        for y in 0..width.saturating_sub(1) {
+            if floor[x][y] && floor[x + 1][y + 1] && !floor[x][y + 1] && !floor[x + 1][y] {
+                floor[x][y + 1] = true;
+            }
+            if floor[x][y + 1] && floor[x + 1][y] && !floor[x][y] && !floor[x + 1][y + 1] {
+                floor[x][y] = true;
+            }
+        }
+    }
+}
+
+// floor cells, which are connected by sides, the order of regions is the order of their first cells
+pub fn floor_regions(floor: &[Vec<bool>]) -> Vec<Vec<(usize, usize)>> {
+    let height = floor.len();
+    let width = if height > 0 { floor[0].len() } else { 0 };
+    let mut is_visited: Vec<Vec<bool>> = vec![vec![false; width]; height];
+    let mut regions: Vec<Vec<(usize, usize)>> = Vec::new();
+    for x in 0..height {
+        for y in 0..width {
+            if !floor[x][y] || is_visited[x][y] {
+                continue;
+            }
+
+            let mut region: Vec<(usize, usize)> = Vec::new();
+            let mut stack: Vec<(usize, usize)> = vec![(x, y)];
+            is_visited[x][y] = true;
+            while let Some((cx, cy)) = stack.pop() {
+                region.push((cx, cy));
+                let neighbours = [(cx.wrapping_sub(1), cy), (cx + 1, cy), (cx, cy.wrapping_sub(1)), (cx, cy + 1)];
+                for (nx, ny) in neighbours {
+                    if nx < height && ny < width && floor[nx][ny] && !is_visited[nx][ny] {
+                        is_visited[nx][ny] = true;
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+            regions.push(region);
+        }
+    }
+    return regions;
+}
@@ -1,25 +1,40 @@
 #[path = "path_finder.rs"] mod path_finder;
+#[path = "room_graph.rs"] mod room_graph;
 
 use crate::level::point::Point;
-use crate::level::options::{PathFinderTile, Options};
+use crate::level::options::{PathFinderTile, Options, ConnectionStrategy};
 use crate::level_generator::room_generator::{Room};
+use crate::level_generator::bsp::BspTree;
 use path_finder::PathFinder;
+use room_graph::{minimum_spanning_tree, gabriel_graph, squared_distance, longest_shortest_path, DisjointSet};
 
 use std::cmp::Ordering;
+use std::collections::VecDeque;
 
 #[derive(Debug)]
 pub struct Corridor {
-    tiles: Vec<Point>
+    tiles: Vec<Point>,
+    rooms: (usize, usize),
+    is_loop: bool
 }
 
 impl Corridor {
-    pub fn new(points: Vec<Point>) -> Corridor {
-        return Corridor { tiles: points };
+    pub fn new(points: Vec<Point>, rooms: (usize, usize), is_loop: bool) -> Corridor {
+        return Corridor { tiles: points, rooms, is_loop };
     }
 
     pub fn get_tiles(&self) -> &Vec<Point> {
         return &self.tiles;
     }
+
+    // indices of connected rooms
+    pub fn rooms(&self) -> (usize, usize) {
+        return self.rooms;
+    }
+
+    pub fn is_loop(&self) -> bool {
+        return self.is_loop;
+    }
 }
 
 fn calculate_distance_between_2_points(origin: &Point, point: &Point) -> f64 {
@@ -29,7 +44,33 @@ fn calculate_distance_between_2_points(origin: &Point, point: &Point) -> f64 {
     return ((xa_minux_xb_squared + ya_minus_yb_squared) as f64).sqrt();
 }
 
-pub fn generate_pathing_grid(rooms: &Vec<Room>, options: &Options) -> Vec<Vec<PathFinderTile>> {
+// true if there is a floor tile of the room not further than distance from the tile with given offsets
+fn is_near_floor(room: &Room, x_offset: i32, y_offset: i32, distance: usize) -> bool {
+    let d = distance as i32;
+    for x in (x_offset - d).max(0)..=(x_offset + d) {
+        for y in (y_offset - d).max(0)..=(y_offset + d) {
+            if room.is_floor(x as usize, y as usize) {
+                return true;
+            }
+        }
+    }
+    return false;
+}
+
+// true if the tile is the door of the room or lies in front of the door not further than distance
+fn is_door_approach(room: &Room, x: i32, y: i32, distance: usize) -> bool {
+    for door in room.doors() {
+        let (dx, dy) = room.door_direction(door);
+        for step in 0..=(distance as i32) {
+            if door.x() + dx * step == x && door.y() + dy * step == y {
+                return true;
+            }
+        }
+    }
+    return false;
+}
+
+pub fn generate_pathing_grid(rooms: &[Room], options: &Options, room_border: usize) -> Vec<Vec<PathFinderTile>> {
     let mut pathable_level: Vec<Vec<PathFinderTile>> = Vec::with_capacity(options.level_height());
     for x in 0..options.level_height() {
         let mut x_array: Vec<PathFinderTile> = Vec::with_capacity(options.level_width());
@@ -49,8 +90,8 @@ pub fn generate_pathing_grid(rooms: &Vec<Room>, options: &Options) -> Vec<Vec<Pa
 
     for r_index in 0..rooms.len() {
         let room = &rooms[r_index];
-        for x_offset in -(options.room_border() as i32)..((room.height() + options.room_border()) as i32) {
-            for y_offset in -(options.room_border() as i32)..((room.width() + options.room_border()) as i32) {
+        for x_offset in -(room_border as i32)..((room.height() + room_border) as i32) {
+            for y_offset in -(room_border as i32)..((room.width() + room_border) as i32) {
                 x = (room.position().x() + x_offset) as usize;
                 y = (room.position().y() + y_offset) as usize;
 
@@ -61,8 +102,33 @@ pub fn generate_pathing_grid(rooms: &Vec<Room>, options: &Options) -> Vec<Vec<Pa
                     continue;
                 }
                 
+                if !room.doors().is_empty() {
+                    // corridors come to the room only through doors, straight from the outside
+                    pathable_level[x][y] = if is_door_approach(room, x as i32, y as i32, room_border) { PathFinderTile::Pathable } else { PathFinderTile::Blocked };
+                    continue;
+                }
+
+                if room.is_composite() {
+                    // composite rooms block only tiles near the floor, so corridors can go through empty parts of the bounding box
+                    // corridors come to the room along the row and column of the entrance
+                    if !is_near_floor(room, x_offset, y_offset, room_border) {
+                        continue;
+                    }
+                    let entrance = room.entrance();
+                    pathable_level[x][y] = if x == entrance.x() as usize || y == entrance.y() as usize { PathFinderTile::Pathable } else { PathFinderTile::Blocked };
+                    continue;
+                }
+
+                // corridors come to the room along the center row and column, but can not go through solid parts of the room
                 let room_center = room.center();
-                if x == room_center.x() as usize || y == room_center.y() as usize {
+                let is_inside = x_offset >= 0 && y_offset >= 0 && x_offset < room.height() as i32 && y_offset < room.width() as i32;
+                // rooms with the solid center (ring) are entered on one side, so corridors go around the center along the floor
+                let has_solid_center = !room.is_floor((room_center.x() - room.position().x()) as usize, (room_center.y() - room.position().y()) as usize);
+                if has_solid_center && is_inside && room.is_floor(x_offset as usize, y_offset as usize) {
+                    pathable_level[x][y] = PathFinderTile::Pathable;
+                    continue;
+                }
+                if (x == room_center.x() as usize || y == room_center.y() as usize) && (!is_inside || room.is_floor(x_offset as usize, y_offset as usize)) {
                     pathable_level[x][y] = PathFinderTile::Pathable;
                     continue;
                 }
@@ -75,14 +141,254 @@ pub fn generate_pathing_grid(rooms: &Vec<Room>, options: &Options) -> Vec<Vec<Pa
     return pathable_level;
 }
 
-pub fn generate_corridors(rooms: &mut Vec<Room>, options: &Options) -> Vec<Corridor> {
-    if rooms.len() <= 1 {
+fn connect_rooms(pathfinder: &mut PathFinder, start: &Point, end: &Point, rooms: (usize, usize), is_loop: bool) -> Option<Corridor> {
+    let path: Vec<Point> = pathfinder.find_path(start, end);
+
+    let corridor_length = path.len();
+    if corridor_length == 0 {
+        return None;
+    }
+
+    // block tiles at the corners of the corridor, so next corridors do not touch it diagonally
+    for i in 1..corridor_length - 1 {
+        let p = &path[i];
+        let prev_p = &path[i - 1];
+        let next_p = &path[i + 1];
+        let x = p.x();
+        let y = p.y();
+        let prev_x: i32 = prev_p.x();
+        let prev_y: i32 = prev_p.y();
+        let next_x: i32 = next_p.x();
+        let next_y: i32 = next_p.y();
+        if prev_x == x && x != next_x {
+            if prev_y < y {
+                if x < next_x {
+                    pathfinder.block_point(Point::new(x, y + 1));
+                }
+                else {
+                    pathfinder.block_point(Point::new(x + 1, y + 1));
+                }
+            }
+            else {
+                if x < next_x {
+                    pathfinder.block_point(Point::new(x - 1, y - 1));
+                }
+                else {
+                    pathfinder.block_point(Point::new(x - 1, y + 1));
+                }
+            }
+        }
+        else if prev_y == y && y != next_y {
+            if prev_x < x {
+                if y < next_y {
+                    pathfinder.block_point(Point::new(x + 1, y - 1));
+                }
+                else {
+                    pathfinder.block_point(Point::new(x + 1, y + 1));
+                }
+            }
+            else {
+                if y < next_y {
+                    pathfinder.block_point(Point::new(x - 1, y - 1));
+                }
+                else {
+                    pathfinder.block_point(Point::new(x, y + 1));
+                }
+            }
+        }
+    }
+
+    return Some(Corridor::new(path, rooms, is_loop));
+}
+
+// pairs of room indices which should be connected by corridors
+// for each node of the tree connect the closest rooms from subtrees of its children, from leaves to the root
+// rooms outside of the tree (prefabs) are connected to the closest room
+fn bsp_sibling_connections(rooms: &[Room], tree: &BspTree) -> Vec<(usize, usize)> {
+    let mut leaf_rooms: Vec<Option<usize>> = vec![None; tree.nodes().len()];
+    for i in 0..rooms.len() {
+        if let Some(leaf) = rooms[i].leaf() {
+            leaf_rooms[leaf] = Some(i);
+        }
+    }
+
+    let mut connections: Vec<(usize, usize)> = Vec::new();
+    for index in (0..tree.nodes().len()).rev() {
+        let (first, second) = match tree.nodes()[index].children() {
+            Some(c) => c,
+            None => continue
+        };
+        let first_rooms: Vec<usize> = tree.subtree_leaves(first).iter().filter_map(|&l| leaf_rooms[l]).collect();
+        let second_rooms: Vec<usize> = tree.subtree_leaves(second).iter().filter_map(|&l| leaf_rooms[l]).collect();
+        let mut closest: Option<(i64, usize, usize)> = None;
+        for &a in &first_rooms {
+            for &b in &second_rooms {
+                let d = squared_distance(rooms[a].center(), rooms[b].center());
+                if closest.is_none() || d < closest.unwrap().0 {
+                    closest = Some((d, a, b));
+                }
+            }
+        }
+        if let Some((_, a, b)) = closest {
+            connections.push((a, b));
+        }
+    }
+
+    for i in 0..rooms.len() {
+        if rooms[i].leaf().is_some() {
+            continue;
+        }
+        let closest = (0..rooms.len()).filter(|&j| j != i).min_by_key(|&j| (squared_distance(rooms[i].center(), rooms[j].center()), j));
+        if let Some(j) = closest {
+            connections.push((j, i));
+        }
+    }
+    return connections;
+}
+
+fn room_connections(rooms: &[Room], options: &Options, bsp_tree: Option<&BspTree>) -> Vec<(usize, usize)> {
+    match options.connection_strategy() {
+        ConnectionStrategy::Chain => {
+            let mut connections: Vec<(usize, usize)> = Vec::with_capacity(rooms.len() - 1);
+            for index in 0..rooms.len() - 1 {
+                connections.push((index, index + 1));
+            }
+            return connections;
+        },
+        ConnectionStrategy::MinimumSpanningTree => {
+            let centers: Vec<Point> = rooms.iter().map(|r| r.center().clone()).collect();
+            return minimum_spanning_tree(&centers);
+        },
+        ConnectionStrategy::BspSiblings => {
+            return match bsp_tree {
+                Some(tree) => bsp_sibling_connections(rooms, tree),
+                None => minimum_spanning_tree(&rooms.iter().map(|r| *r.center()).collect::<Vec<Point>>())
+            };
+        }
+    }
+}
+
+// additional pairs of rooms, which create cycles in the level
+// candidates are the shortest edges of the Gabriel graph of room centers, which are not used by the spanning connection
+fn loop_connections(rooms: &[Room], connections: &[(usize, usize)], options: &Options) -> Vec<(usize, usize)> {
+    if options.loop_ratio() <= 0.0 {
         return Vec::new();
     }
 
-    let pathable_level: Vec<Vec<PathFinderTile>> = generate_pathing_grid(rooms, options);
+    let centers: Vec<Point> = rooms.iter().map(|r| r.center().clone()).collect();
+    let candidates: Vec<(usize, usize)> = gabriel_graph(&centers).into_iter().filter(|&(a, b)| {
+        return !connections.iter().any(|&(c, d)| (a == c && b == d) || (a == d && b == c));
+    }).collect();
+
+    let count = (options.loop_ratio() * candidates.len() as f64).round() as usize;
+    return candidates.into_iter().take(count).collect();
+}
+
+// find the path between two rooms, if the search stops by the limit, then the limit is doubled and the search repeats
+fn connect_rooms_with_retries(pathfinder: &mut PathFinder, rooms: &[Room], from: usize, to: usize, is_loop: bool, options: &Options) -> Option<Corridor> {
+    let mut corridor: Option<Corridor> = None;
+    let mut search_limit = options.search_limit();
+    for _ in 0..=options.retry_policy().limit_raises() {
+        pathfinder.set_search_limit(search_limit);
+        let start = rooms[from].entrance_towards(rooms[to].center());
+        let end = rooms[to].entrance_towards(rooms[from].center());
+        corridor = connect_rooms(pathfinder, start, end, (from, to), is_loop);
+        if corridor.is_some() || !pathfinder.limit_reached() {
+            break;
+        }
+        search_limit = search_limit.saturating_mul(2);
+    }
+    pathfinder.set_search_limit(options.search_limit());
+
+    return corridor;
+}
+
+// try to connect disconnected groups of rooms through other pairs of rooms, the closest pairs are used first
+fn connect_groups(pathfinder: &mut PathFinder, rooms: &[Room], groups: &mut DisjointSet, corridors: &mut Vec<Corridor>, options: &Options) {
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    for a in 0..rooms.len() {
+        for b in (a + 1)..rooms.len() {
+            if !groups.is_connected(a, b) {
+                pairs.push((a, b));
+            }
+        }
+    }
+    pairs.sort_by_key(|&(a, b)| (squared_distance(rooms[a].center(), rooms[b].center()), a, b));
+
+    let mut attempts: usize = 0;
+    for (a, b) in pairs {
+        if groups.groups_count() == 1 || attempts >= options.retry_policy().partner_attempts() {
+            break;
+        }
+        if groups.is_connected(a, b) {
+            continue;
+        }
+
+        attempts += 1;
+        if let Some(corridor) = connect_rooms_with_retries(pathfinder, rooms, a, b, false, options) {
+            groups.union(a, b);
+            corridors.push(corridor);
+        }
+    }
+}
+
+// rooms from the start room to the exit room, which are the most distant rooms in the graph of corridors
+pub fn critical_path(rooms_count: usize, corridors: &[Corridor]) -> Vec<usize> {
+    let edges: Vec<(usize, usize)> = corridors.iter().map(|c| c.rooms()).collect();
+    return longest_shortest_path(rooms_count, &edges);
+}
+
+// the critical path of levels without corridors (tunnels and mazes), where rooms are connected by the floor
+// each floor cell belongs to the closest room along the floor, two rooms are connected, if their cells touch each other
+pub fn floor_critical_path(floor: &[Vec<bool>], rooms: &[Room]) -> Vec<usize> {
+    let height = floor.len();
+    let width = if height > 0 { floor[0].len() } else { 0 };
+    let mut owners: Vec<Vec<Option<usize>>> = vec![vec![None; width]; height];
+    let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+    for i in 0..rooms.len() {
+        let position = rooms[i].position();
+        for x in (position.x() as usize)..(position.x() as usize + rooms[i].height()).min(height) {
+            for y in (position.y() as usize)..(position.y() as usize + rooms[i].width()).min(width) {
+                if floor[x][y] && owners[x][y].is_none() {
+                    owners[x][y] = Some(i);
+                    queue.push_back((x, y));
+                }
+            }
+        }
+    }
+
+    let mut edges: Vec<(usize, usize)> = Vec::new();
+    while let Some((x, y)) = queue.pop_front() {
+        let owner = owners[x][y].unwrap_or(0);
+        let neighbours = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+        for (nx, ny) in neighbours {
+            if nx >= height || ny >= width || !floor[nx][ny] {
+                continue;
+            }
+            match owners[nx][ny] {
+                None => {
+                    owners[nx][ny] = Some(owner);
+                    queue.push_back((nx, ny));
+                },
+                Some(other) if other != owner => edges.push((owner.min(other), owner.max(other))),
+                _ => {}
+            }
+        }
+    }
+    edges.sort();
+    edges.dedup();
+    return longest_shortest_path(rooms.len(), &edges);
+}
+
+// return generated corridors and pairs of rooms, which should be connected, but the path between them is not found
+pub fn generate_corridors(rooms: &mut [Room], options: &Options, bsp_tree: Option<&BspTree>) -> (Vec<Corridor>, Vec<(usize, usize)>) {
+    if rooms.len() <= 1 {
+        return (Vec::new(), Vec::new());
+    }
+
+    let pathable_level: Vec<Vec<PathFinderTile>> = generate_pathing_grid(rooms, options, options.room_border());
 
-    let mut pathfinder: PathFinder = PathFinder::new(pathable_level, 2000);
+    let mut pathfinder: PathFinder = PathFinder::new(pathable_level, options.search_limit());
     rooms.sort_by(|room1, room2| {
         let reference = Point::new(0, 0);
         let room1_distance_from_reference = calculate_distance_between_2_points(&reference, room1.center());
@@ -98,69 +404,80 @@ pub fn generate_corridors(rooms: &mut Vec<Room>, options: &Options) -> Vec<Corri
         }
     });
 
-    let mut corridors = Vec::with_capacity(rooms.len() - 1);
-    for index in 0..rooms.len() - 1 {
-        let current_room = &rooms[index];
-        let next_room = &rooms[index + 1];
-
-        let path: Vec<Point> = pathfinder.find_path(current_room.center(), next_room.center());
-
-        let corridor_length = path.len();
-        if corridor_length == 0 {
-            continue;
+    let connections = room_connections(rooms, options, bsp_tree);
+    let loops = loop_connections(rooms, &connections, options);
+    let mut corridors = Vec::with_capacity(connections.len() + loops.len());
+    let mut groups = DisjointSet::new(rooms.len());
+    let mut failed_connections: Vec<(usize, usize)> = Vec::new();
+    for (from, to) in connections {
+        match connect_rooms_with_retries(&mut pathfinder, rooms, from, to, false, options) {
+            Some(corridor) => {
+                groups.union(from, to);
+                corridors.push(corridor);
+            },
+            None => failed_connections.push((from, to))
         }
+    }
 
-        for i in 1..corridor_length - 1 {
-            let p = &path[i];
-            let prev_p = &path[i - 1];
-            let next_p = &path[i + 1];
-            let x = p.x();
-            let y = p.y();
-            let prev_x: i32 = prev_p.x();
-            let prev_y: i32 = prev_p.y();
-            let next_x: i32 = next_p.x();
-            let next_y: i32 = next_p.y();
-            if prev_x == x && x != next_x {
-                if prev_y < y {
-                    if x < next_x {
-                        pathfinder.block_point(Point::new(x, y + 1));
-                    }
-                    else {
-                        pathfinder.block_point(Point::new(x + 1, y + 1));
-                    }
-                }
-                else {
-                    if x < next_x {
-                        pathfinder.block_point(Point::new(x - 1, y - 1));
-                    }
-                    else {
-                        pathfinder.block_point(Point::new(x - 1, y + 1));
-                    }
-                }
+    let retry_policy = options.retry_policy();
+    if groups.groups_count() > 1 && retry_policy.partner_attempts() > 0 {
+        connect_groups(&mut pathfinder, rooms, &mut groups, &mut corridors, options);
+    }
+
+    if groups.groups_count() > 1 && retry_policy.relax_room_borders() {
+        // allow corridors to go close to other rooms
+        let bordered_grid = pathfinder.replace_grid(generate_pathing_grid(rooms, options, 0));
+        for &(from, to) in &failed_connections {
+            if groups.is_connected(from, to) {
+                continue;
             }
-            else if prev_y == y && y != next_y {
-                if prev_x < x {
-                    if y < next_y {
-                        pathfinder.block_point(Point::new(x + 1, y - 1));
-                    }
-                    else {
-                        pathfinder.block_point(Point::new(x + 1, y + 1));
-                    }
-                }
-                else {
-                    if y < next_y {
-                        pathfinder.block_point(Point::new(x - 1, y - 1));
-                    }
-                    else {
-                        pathfinder.block_point(Point::new(x, y + 1));
-                    }
-                }
+            if let Some(corridor) = connect_rooms_with_retries(&mut pathfinder, rooms, from, to, false, options) {
+                groups.union(from, to);
+                corridors.push(corridor);
             }
         }
-        let corridor = Corridor::new(path);
 
-        corridors.push(corridor);
+        if groups.groups_count() > 1 && retry_policy.partner_attempts() > 0 {
+            connect_groups(&mut pathfinder, rooms, &mut groups, &mut corridors, options);
+        }
+
+        // loops are not needed for connectivity, so they keep room borders
+        pathfinder.replace_grid(bordered_grid);
     }
 
-    return corridors;
-}
\ No newline at end of file
+    for (from, to) in loops {
+        // the corridor is a loop only if it connects already connected rooms
+        let is_loop = groups.is_connected(from, to);
+        if let Some(corridor) = connect_rooms_with_retries(&mut pathfinder, rooms, from, to, is_loop, options) {
+            groups.union(from, to);
+            corridors.push(corridor);
+        }
+    }
+
+    failed_connections.retain(|&(from, to)| !groups.is_connected(from, to));
+
+    return (corridors, failed_connections);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::options::RoomType;
+
+    #[test]
+    fn floor_critical_path_follows_floor_connections() {
+        // rooms 0, 2 and 1 in one row, the floor line connects them in this order, room 3 is not connected
+        let mut floor: Vec<Vec<bool>> = vec![vec![false; 20]; 8];
+        for y in 1..19 {
+            floor[2][y] = true;
+        }
+        floor[6][10] = true;
+        let rooms = vec![
+            Room::new(3, 3, 1, 1, RoomType::Square),
+            Room::new(3, 3, 1, 16, RoomType::Square),
+            Room::new(3, 3, 1, 8, RoomType::Square),
+            Room::new(1, 1, 6, 10, RoomType::Square)
+        ];
+        assert_eq!(floor_critical_path(&floor, &rooms), vec![0, 2, 1]);
+    }
+}
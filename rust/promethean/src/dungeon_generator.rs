@@ -0,0 +1,252 @@
+use super::LevelGenerator;
+use crate::level::Level;
+use crate::level::point::Point;
+use crate::level::options::{OptionsError, Tile};
+use js_sys::Int32Array;
+
+use wasm_bindgen::prelude::*;
+
+// how many times the next floor is generated again with other seeds, when stairs can not be placed
+const STAIRS_ATTEMPTS: usize = 16;
+
+// seed of the floor, the first floor uses the dungeon seed, other seeds are mixed by the splitmix64 step
+// the seed is cut to 32 bits, so native and WASM builds generate the same dungeon
+pub fn floor_seed(dungeon_seed: usize, floor: usize, attempt: usize) -> usize {
+    if floor == 0 && attempt == 0 {
+        return dungeon_seed;
+    }
+    let index = ((floor as u64) << 16) | attempt as u64;
+    let mut z = (dungeon_seed as u64).wrapping_add(index.wrapping_add(1).wrapping_mul(0x9E3779B97F4A7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^= z >> 31;
+    return (z & 0xFFFF_FFFF) as usize;
+}
+
+// floors of the dungeon, stairs[k] is the position of down stairs on the floor k and up stairs on the floor k + 1
+#[derive(Debug, Clone)]
+#[wasm_bindgen]
+pub struct Dungeon {
+    floors: Vec<Level>,
+    seeds: Vec<usize>,
+    stairs: Vec<Point>
+}
+
+impl Dungeon {
+    pub fn floors_inner(&self) -> &Vec<Level> {
+        return &self.floors;
+    }
+
+    pub fn seeds_inner(&self) -> &Vec<usize> {
+        return &self.seeds;
+    }
+
+    pub fn stairs_inner(&self) -> &Vec<Point> {
+        return &self.stairs;
+    }
+}
+
+#[wasm_bindgen]
+impl Dungeon {
+    #[wasm_bindgen(getter)]
+    pub fn floors_count(&self) -> usize {
+        return self.floors.len();
+    }
+
+    #[wasm_bindgen]
+    pub fn floor(&self, index: usize) -> Option<Level> {
+        return self.floors.get(index).cloned();
+    }
+
+    // the seed, which is used for the floor
+    #[wasm_bindgen]
+    pub fn floor_seed(&self, index: usize) -> Option<usize> {
+        return self.seeds.get(index).copied();
+    }
+
+    // plain array with pairs of stairs coordinates, one pair for each two neighbouring floors
+    #[wasm_bindgen(getter)]
+    pub fn stairs(&self) -> Int32Array {
+        let mut to_return: Vec<i32> = Vec::with_capacity(2 * self.stairs.len());
+        for p in &self.stairs {
+            to_return.push(p.x());
+            to_return.push(p.y());
+        }
+
+        return Int32Array::from(to_return.as_slice());
+    }
+}
+
+// generates floors by the level generator, each floor with its own seed
+#[derive(Debug)]
+#[wasm_bindgen]
+pub struct DungeonGenerator {
+    generator: LevelGenerator,
+    floors_count: usize
+}
+
+#[wasm_bindgen]
+impl DungeonGenerator {
+    // the generator is moved into the dungeon generator, all its options, shapes and prefabs are used for each floor
+    #[wasm_bindgen(constructor)]
+    pub fn new(generator: LevelGenerator, floors_count: usize) -> DungeonGenerator {
+        return DungeonGenerator { generator, floors_count };
+    }
+
+    #[wasm_bindgen]
+    pub fn set_floors_count(&mut self, floors_count: usize) {
+        self.floors_count = floors_count;
+    }
+
+    // down stairs on the floor k and up stairs on the floor k + 1 have the same coordinates and are inside rooms of both floors
+    // down stairs are reachable from up stairs of the same floor, on the first floor they are in the largest region
+    // if stairs can not be placed, then the next floor is generated again with the other seed
+    // floors change the seed of the level generator, so it is restored at the end and the next call generates the same dungeon
+    #[wasm_bindgen]
+    pub fn generate(&mut self) -> Result<Dungeon, OptionsError> {
+        if self.floors_count == 0 {
+            return Err(OptionsError::NoFloors);
+        }
+        let dungeon_seed = self.generator.options.random_seed();
+        let dungeon = self.generate_floors(dungeon_seed);
+        self.generator.reseed(dungeon_seed);
+        return dungeon;
+    }
+}
+
+impl DungeonGenerator {
+    pub fn generator_mut(&mut self) -> &mut LevelGenerator {
+        return &mut self.generator;
+    }
+
+    fn generate_floors(&mut self, dungeon_seed: usize) -> Result<Dungeon, OptionsError> {
+        let mut floors: Vec<Level> = Vec::with_capacity(self.floors_count);
+        let mut seeds: Vec<usize> = Vec::with_capacity(self.floors_count);
+        let mut stairs: Vec<Point> = Vec::with_capacity(self.floors_count - 1);
+
+        self.generator.reseed(dungeon_seed);
+        let first = self.generator.generate()?;
+        let (labels, sizes) = walkable_regions(&first);
+        let largest = (0..sizes.len()).max_by_key(|&i| sizes[i]);
+        let mut reachable: Vec<bool> = labels.iter().map(|&l| Some(l) == largest).collect();
+        floors.push(first);
+        seeds.push(dungeon_seed);
+
+        for floor in 1..self.floors_count {
+            let mut is_placed = false;
+            for attempt in 0..STAIRS_ATTEMPTS {
+                let seed = floor_seed(dungeon_seed, floor, attempt);
+                self.generator.reseed(seed);
+                let mut level = self.generator.generate()?;
+                let previous = &floors[floor - 1];
+                let candidates: Vec<Point> = stairs_positions(previous).into_iter().filter(|p| {
+                    return reachable[p.x() as usize * previous.width() + p.y() as usize] && stairs_positions_contain(&level, p);
+                }).collect();
+                if candidates.is_empty() {
+                    continue;
+                }
+
+                let position = candidates[self.generator.room_generator.random_mut().next(0, candidates.len() - 1)];
+                floors[floor - 1].set_from_point(&position, Tile::StairsDown);
+                level.set_from_point(&position, Tile::StairsUp);
+                let (labels, _) = walkable_regions(&level);
+                let label = labels[position.x() as usize * level.width() + position.y() as usize];
+                reachable = labels.iter().map(|&l| l == label).collect();
+                stairs.push(position);
+                floors.push(level);
+                seeds.push(seed);
+                is_placed = true;
+                break;
+            }
+            if !is_placed {
+                return Err(OptionsError::StairsNotPlaced { floor, attempts: STAIRS_ATTEMPTS });
+            }
+        }
+
+        return Ok(Dungeon { floors, seeds, stairs });
+    }
+}
+
+// labels of 4-connected walkable regions for each tile (usize::MAX for walls) and sizes of regions
+fn walkable_regions(level: &Level) -> (Vec<usize>, Vec<usize>) {
+    let height = level.height();
+    let width = level.width();
+    let mut labels: Vec<usize> = vec![usize::MAX; height * width];
+    let mut sizes: Vec<usize> = Vec::new();
+    for (x, y, tile) in level.iter() {
+        if !tile.is_walkable() || labels[x * width + y] != usize::MAX {
+            continue;
+        }
+
+        let label = sizes.len();
+        let mut size: usize = 0;
+        let mut stack: Vec<(usize, usize)> = vec![(x, y)];
+        labels[x * width + y] = label;
+        while let Some((cx, cy)) = stack.pop() {
+            size += 1;
+            let neighbours = [(cx.wrapping_sub(1), cy), (cx + 1, cy), (cx, cy.wrapping_sub(1)), (cx, cy + 1)];
+            for (nx, ny) in neighbours {
+                if nx < height && ny < width && labels[nx * width + ny] == usize::MAX && level.get_from_coordinates(nx, ny).is_walkable() {
+                    labels[nx * width + ny] = label;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        sizes.push(size);
+    }
+    return (labels, sizes);
+}
+
+// floor tiles inside rooms, which are not doors, only centers of inflated cells are used
+// levels without rooms (caves and wave function collapse) use all floor tiles
+fn stairs_positions(level: &Level) -> Vec<Point> {
+    let mut to_return: Vec<Point> = Vec::new();
+    for x in (1..level.height()).step_by(2) {
+        for y in (1..level.width()).step_by(2) {
+            let point = Point::new(x as i32, y as i32);
+            if stairs_positions_contain(level, &point) {
+                to_return.push(point);
+            }
+        }
+    }
+    return to_return;
+}
+
+fn stairs_positions_contain(level: &Level, point: &Point) -> bool {
+    if level.get_from_coordinates(point.x() as usize, point.y() as usize) != Tile::Floor {
+        return false;
+    }
+    let statistics = level.statistics_inner();
+    let rooms = statistics.rooms_inner();
+    if rooms.is_empty() {
+        return true;
+    }
+    // the floor inside the bounding box of composite, ring, cavern and prefab rooms can be the corridor, so the room mask is used
+    return rooms.iter().any(|r| {
+        return r.is_floor(point) && !r.doors_inner().iter().any(|d| d.x() == point.x() && d.y() == point.y());
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::options::{OptionsBuilder, RoomType};
+
+    // floor tiles in empty parts of bounding boxes of ring and composite rooms can be corridors, they are not used for stairs
+    #[test]
+    fn stairs_positions_are_on_room_floor() {
+        for room_type in [RoomType::Ring, RoomType::LShape] {
+            for seed in 1..=10 {
+                let options = OptionsBuilder::new().random_seed(seed).room_types(vec![room_type]).build().unwrap();
+                let level = LevelGenerator::new_with_options(options).generate().unwrap();
+                let rooms = level.statistics_inner().rooms_inner();
+                for room in rooms.iter().filter(|r| r.room_type() == RoomType::Ring) {
+                    assert!(!room.is_floor(room.center()));
+                }
+                for point in stairs_positions(&level) {
+                    assert!(rooms.iter().any(|r| r.is_floor(&point)), "seed {} with {:?} at {}", seed, room_type, point);
+                }
+            }
+        }
+    }
+}
@@ -2,21 +2,300 @@
 #[path = "options.rs"] pub mod options;
 
 use std::fmt;
-use js_sys::Array;
+use js_sys::{Int32Array, Uint32Array, Uint8Array};
 
 use point::Point;
-use options::Tile;
+use options::{RoomType, Tile};
 
 use wasm_bindgen::prelude::*;
 
+// room bounding box in the coordinates of the output level
+#[derive(Debug, Clone)]
+#[wasm_bindgen]
+pub struct RoomInfo {
+    index: usize,
+    position: Point,
+    height: usize,
+    width: usize,
+    center: Point,
+    room_type: RoomType,
+    orientation: usize,
+    doors: Vec<Point>,
+    // floor mask of the room before the level is inflated
+    floor: Vec<Vec<bool>>
+}
+
+impl RoomInfo {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(index: usize, position: Point, height: usize, width: usize, center: Point, room_type: RoomType, orientation: usize, doors: Vec<Point>, floor: Vec<Vec<bool>>) -> RoomInfo {
+        return RoomInfo { index, position, height, width, center, room_type, orientation, doors, floor };
+    }
+
+    // true, if the point of the level is the floor of the room, empty parts of the bounding box are not the room
+    pub fn is_floor(&self, point: &Point) -> bool {
+        if point.x() < self.position.x() || point.y() < self.position.y() {
+            return false;
+        }
+        let x_offset = ((point.x() - self.position.x()) / 2) as usize;
+        let y_offset = ((point.y() - self.position.y()) / 2) as usize;
+        return self.floor.get(x_offset).and_then(|row| row.get(y_offset)).copied().unwrap_or(false);
+    }
+
+    pub fn doors_inner(&self) -> &Vec<Point> {
+        return &self.doors;
+    }
+
+    pub fn position(&self) -> &Point {
+        return &self.position;
+    }
+
+    pub fn center(&self) -> &Point {
+        return &self.center;
+    }
+
+    pub fn room_type(&self) -> RoomType {
+        return self.room_type;
+    }
+}
+
+#[wasm_bindgen]
+impl RoomInfo {
+    #[wasm_bindgen(getter)]
+    pub fn index(&self) -> usize {
+        return self.index;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> i32 {
+        return self.position.x();
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> i32 {
+        return self.position.y();
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn center_x(&self) -> i32 {
+        return self.center.x();
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn center_y(&self) -> i32 {
+        return self.center.y();
+    }
+
+    #[wasm_bindgen(getter = room_type)]
+    pub fn room_type_id(&self) -> u8 {
+        return self.room_type.id();
+    }
+
+    // rotation and mirroring of composite rooms from 0 to 7, always 0 for other rooms
+    #[wasm_bindgen(getter)]
+    pub fn orientation(&self) -> usize {
+        return self.orientation;
+    }
+
+    // the index of the custom shape in the generator, undefined for built-in shapes
+    #[wasm_bindgen(getter)]
+    pub fn custom_shape(&self) -> Option<usize> {
+        return match self.room_type {
+            RoomType::Custom(index) => Some(index),
+            _ => None
+        };
+    }
+
+    // the index of the prefab in the generator, undefined for other rooms
+    #[wasm_bindgen(getter)]
+    pub fn prefab(&self) -> Option<usize> {
+        return match self.room_type {
+            RoomType::Prefab(index) => Some(index),
+            _ => None
+        };
+    }
+
+    // door positions as flat array [x1, y1, x2, y2, ...], empty for rooms without doors
+    #[wasm_bindgen(getter)]
+    pub fn doors(&self) -> Int32Array {
+        let coordinates: Vec<i32> = self.doors.iter().flat_map(|p| [p.x(), p.y()]).collect();
+        return Int32Array::from(coordinates.as_slice());
+    }
+}
+
+// corridor tiles in the coordinates of the output level and indices of connected rooms
+#[derive(Debug, Clone)]
+#[wasm_bindgen]
+pub struct CorridorInfo {
+    index: usize,
+    rooms: (usize, usize),
+    points: Vec<Point>,
+    is_loop: bool
+}
+
+impl CorridorInfo {
+    pub fn new(index: usize, rooms: (usize, usize), points: Vec<Point>, is_loop: bool) -> CorridorInfo {
+        return CorridorInfo { index, rooms, points, is_loop };
+    }
+
+    pub fn rooms(&self) -> (usize, usize) {
+        return self.rooms;
+    }
+
+    pub fn points_inner(&self) -> &Vec<Point> {
+        return &self.points;
+    }
+}
+
+#[wasm_bindgen]
+impl CorridorInfo {
+    #[wasm_bindgen(getter)]
+    pub fn index(&self) -> usize {
+        return self.index;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn start_room(&self) -> usize {
+        return self.rooms.0;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn end_room(&self) -> usize {
+        return self.rooms.1;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn is_loop(&self) -> bool {
+        return self.is_loop;
+    }
+
+    // plain array with corridor points from the start room to the end room
+    #[wasm_bindgen(getter)]
+    pub fn points(&self) -> Int32Array {
+        let mut to_return: Vec<i32> = Vec::with_capacity(2 * self.points.len());
+        for p in &self.points {
+            to_return.push(p.x());
+            to_return.push(p.y());
+        }
+
+        return Int32Array::from(to_return.as_slice());
+    }
+}
+
+// the node of the BSP tree in the coordinates of the output level
+#[derive(Debug, Clone)]
+#[wasm_bindgen]
+pub struct BspNodeInfo {
+    index: usize,
+    position: Point,
+    height: usize,
+    width: usize,
+    parent: Option<usize>,
+    children: Option<(usize, usize)>,
+    room: Option<usize>
+}
+
+impl BspNodeInfo {
+    pub fn new(index: usize, position: Point, height: usize, width: usize, parent: Option<usize>, children: Option<(usize, usize)>, room: Option<usize>) -> BspNodeInfo {
+        return BspNodeInfo { index, position, height, width, parent, children, room };
+    }
+
+    pub fn position(&self) -> &Point {
+        return &self.position;
+    }
+
+    pub fn children(&self) -> Option<(usize, usize)> {
+        return self.children;
+    }
+}
+
+#[wasm_bindgen]
+impl BspNodeInfo {
+    #[wasm_bindgen(getter)]
+    pub fn index(&self) -> usize {
+        return self.index;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> i32 {
+        return self.position.x();
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> i32 {
+        return self.position.y();
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    // undefined for the root
+    #[wasm_bindgen(getter)]
+    pub fn parent(&self) -> Option<usize> {
+        return self.parent;
+    }
+
+    // undefined for leaves
+    #[wasm_bindgen(getter)]
+    pub fn first_child(&self) -> Option<usize> {
+        return self.children.map(|c| c.0);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn second_child(&self) -> Option<usize> {
+        return self.children.map(|c| c.1);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn is_leaf(&self) -> bool {
+        return self.children.is_none();
+    }
+
+    // the index of the room in the leaf, undefined for other nodes and leaves without rooms
+    #[wasm_bindgen(getter)]
+    pub fn room(&self) -> Option<usize> {
+        return self.room;
+    }
+}
+
 #[derive(Debug, Clone)]
 #[wasm_bindgen]
 pub struct LevelStatistics {
     init: bool,
     rooms_count: usize,
     corridors_count: usize,
+    loop_corridors_count: usize,
     all_corridors: bool,
-    room_centers: Vec<Point>
+    room_centers: Vec<Point>,
+    failed_connections: Vec<(usize, usize)>,
+    rooms: Vec<RoomInfo>,
+    requested_rooms_count: usize,
+    placed_rooms_count: usize,
+    regions_count: usize,
+    dead_ends_count: usize,
+    restarts_count: usize
+}
+
+impl Default for LevelStatistics {
+    fn default() -> Self {
+        return Self::new();
+    }
 }
 
 impl LevelStatistics {
@@ -25,26 +304,68 @@ impl LevelStatistics {
             init: false,
             rooms_count: 0,
             corridors_count: 0,
+            loop_corridors_count: 0,
             all_corridors: false,
-            room_centers: Vec::new()
+            room_centers: Vec::new(),
+            failed_connections: Vec::new(),
+            rooms: Vec::new(),
+            requested_rooms_count: 0,
+            placed_rooms_count: 0,
+            regions_count: 0,
+            dead_ends_count: 0,
+            restarts_count: 0
         };
     }
 
     pub fn set(&mut self, 
         rooms_count: usize, 
         corridors_count: usize, 
+        loop_corridors_count: usize, 
         all_corridors: bool, 
-        room_centers: Vec<Point>) {
+        room_centers: Vec<Point>,
+        failed_connections: Vec<(usize, usize)>) {
         self.init = true;
         self.rooms_count = rooms_count;
         self.corridors_count = corridors_count;
+        self.loop_corridors_count = loop_corridors_count;
         self.all_corridors = all_corridors;
         self.room_centers = room_centers;
+        self.failed_connections = failed_connections;
     }
 
     pub fn room_centers_inner(&self) -> &Vec<Point> {
         return &self.room_centers;
     }
+
+    pub fn failed_connections_inner(&self) -> &Vec<(usize, usize)> {
+        return &self.failed_connections;
+    }
+
+    pub fn set_rooms(&mut self, rooms: Vec<RoomInfo>) {
+        self.rooms = rooms;
+    }
+
+    pub fn rooms_inner(&self) -> &Vec<RoomInfo> {
+        return &self.rooms;
+    }
+
+    // placed rooms do not include prefabs
+    pub fn set_requested_rooms(&mut self, requested_rooms_count: usize, placed_rooms_count: usize) {
+        self.requested_rooms_count = requested_rooms_count;
+        self.placed_rooms_count = placed_rooms_count;
+    }
+
+    pub fn set_regions_count(&mut self, regions_count: usize) {
+        self.regions_count = regions_count;
+    }
+
+    pub fn set_dead_ends_count(&mut self, dead_ends_count: usize) {
+        self.dead_ends_count = dead_ends_count;
+    }
+
+    pub fn set_restarts_count(&mut self, restarts_count: usize) {
+        self.restarts_count = restarts_count;
+    }
 }
 
 #[wasm_bindgen]
@@ -59,96 +380,225 @@ impl LevelStatistics {
         return self.corridors_count;
     }
 
+    #[wasm_bindgen(getter)]
+    pub fn loop_corridors_count(&self) -> usize {
+        return self.loop_corridors_count;
+    }
+
+    // true, if there are no failed connections, levels without corridors report their connectivity by regions_count
     #[wasm_bindgen(getter)]
     pub fn all_corridors(&self) -> bool {
         return self.all_corridors;
     }
 
+    // the number of rooms from the options, prefabs are not included
     #[wasm_bindgen(getter)]
-    pub fn room_centers(&self) -> Array {
+    pub fn requested_rooms_count(&self) -> usize {
+        return self.requested_rooms_count;
+    }
+
+    // the number of generated rooms without prefabs
+    #[wasm_bindgen(getter)]
+    pub fn placed_rooms_count(&self) -> usize {
+        return self.placed_rooms_count;
+    }
+
+    // how many requested rooms do not fit into the level
+    #[wasm_bindgen(getter)]
+    pub fn rooms_shortfall(&self) -> usize {
+        return self.requested_rooms_count.saturating_sub(self.placed_rooms_count);
+    }
+
+    // the number of separate floor regions of the cave level, 0 for levels with rooms
+    #[wasm_bindgen(getter)]
+    pub fn regions_count(&self) -> usize {
+        return self.regions_count;
+    }
+
+    // the number of dead ends of the maze level, 0 for other levels
+    #[wasm_bindgen(getter)]
+    pub fn dead_ends_count(&self) -> usize {
+        return self.dead_ends_count;
+    }
+
+    // how many times the wave function collapse starts again after contradictions, 0 for other levels
+    #[wasm_bindgen(getter)]
+    pub fn restarts_count(&self) -> usize {
+        return self.restarts_count;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn room_centers(&self) -> Int32Array {
         let mut to_return: Vec<i32> = Vec::with_capacity(2 * self.room_centers.len());
         for p in &self.room_centers {
             to_return.push(p.x());
             to_return.push(p.y());
         }
 
-        return to_return.into_iter().map(JsValue::from).collect();
+        return Int32Array::from(to_return.as_slice());
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn failed_connections(&self) -> Uint32Array {
+        let mut to_return: Vec<u32> = Vec::with_capacity(2 * self.failed_connections.len());
+        for &(a, b) in &self.failed_connections {
+            to_return.push(a as u32);
+            to_return.push(b as u32);
+        }
+
+        return Uint32Array::from(to_return.as_slice());
+    }
+
+    #[wasm_bindgen]
+    pub fn room(&self, index: usize) -> Option<RoomInfo> {
+        return self.rooms.get(index).cloned();
     }
 }
 
+// tiles are stored in one buffer row by row, x is the row index, y is the column index
 #[derive(Debug, Clone)]
 #[wasm_bindgen]
 pub struct Level {
     height: usize,
     width: usize,
-    level: Vec<Vec<Tile>>,
-    statistics: LevelStatistics
+    tiles: Vec<Tile>,
+    statistics: LevelStatistics,
+    corridors: Vec<CorridorInfo>,
+    bsp_nodes: Vec<BspNodeInfo>,
+    critical_path: Vec<usize>
 }
 
 impl Level {
     pub fn new(height: usize, width: usize) -> Level {
-        let mut level: Vec<Vec<Tile>> = Vec::with_capacity(height);
-        for _x in 0..height {
-            let mut row: Vec<Tile> = Vec::with_capacity(width);
-            for _y in 0..width {
-                row.push(Tile::Empty);
-            }
-            level.push(row);
-        }
-
         return Level {
             height,
             width,
-            level,
-            statistics: LevelStatistics::new()
+            tiles: vec![Tile::Empty; height * width],
+            statistics: LevelStatistics::new(),
+            corridors: Vec::new(),
+            bsp_nodes: Vec::new(),
+            critical_path: Vec::new()
         };
     }
 
     pub fn set_statistics(&mut self, rooms_count: usize, 
                                      corridors_count: usize, 
+                                     loop_corridors_count: usize, 
                                      all_corridors: bool, 
-                                     room_centers: Vec<Point>) {
-        self.statistics.set(rooms_count, corridors_count, all_corridors, room_centers);
+                                     room_centers: Vec<Point>,
+                                     failed_connections: Vec<(usize, usize)>) {
+        self.statistics.set(rooms_count, corridors_count, loop_corridors_count, all_corridors, room_centers, failed_connections);
+    }
+
+    pub fn set_rooms(&mut self, rooms: Vec<RoomInfo>) {
+        self.statistics.set_rooms(rooms);
+    }
+
+    pub fn set_requested_rooms(&mut self, requested_rooms_count: usize, placed_rooms_count: usize) {
+        self.statistics.set_requested_rooms(requested_rooms_count, placed_rooms_count);
+    }
+
+    pub fn set_regions_count(&mut self, regions_count: usize) {
+        self.statistics.set_regions_count(regions_count);
+    }
+
+    pub fn set_dead_ends_count(&mut self, dead_ends_count: usize) {
+        self.statistics.set_dead_ends_count(dead_ends_count);
+    }
+
+    pub fn set_restarts_count(&mut self, restarts_count: usize) {
+        self.statistics.set_restarts_count(restarts_count);
+    }
+
+    pub fn set_corridors(&mut self, corridors: Vec<CorridorInfo>) {
+        self.corridors = corridors;
+    }
+
+    pub fn statistics_inner(&self) -> &LevelStatistics {
+        return &self.statistics;
+    }
+
+    pub fn corridors_inner(&self) -> &Vec<CorridorInfo> {
+        return &self.corridors;
+    }
+
+    pub fn set_bsp_nodes(&mut self, bsp_nodes: Vec<BspNodeInfo>) {
+        self.bsp_nodes = bsp_nodes;
+    }
+
+    // empty if rooms are not placed by the BSP, the first node is the root
+    pub fn bsp_nodes_inner(&self) -> &Vec<BspNodeInfo> {
+        return &self.bsp_nodes;
+    }
+
+    pub fn set_critical_path(&mut self, critical_path: Vec<usize>) {
+        self.critical_path = critical_path;
+    }
+
+    // room indices from the start room to the exit room, empty for levels without corridors
+    pub fn critical_path_inner(&self) -> &Vec<usize> {
+        return &self.critical_path;
+    }
+
+    // edges of the room graph, each corridor connects two rooms
+    pub fn room_connections_inner(&self) -> Vec<(usize, usize)> {
+        return self.corridors.iter().map(|c| c.rooms()).collect();
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        return x * self.width + y;
     }
 
     pub fn set_tile(&mut self, x: usize, y: usize, tile: Tile) {
-        self.level[x][y] = tile;
+        let index = self.index(x, y);
+        self.tiles[index] = tile;
     }
 
     pub fn get_from_coordinates(&self, x: usize, y: usize) -> Tile {
-        return self.level[x][y];
+        return self.tiles[self.index(x, y)];
     }
 
     pub fn set_from_point(&mut self, point: &Point, value: Tile) {
         self.set_tile(point.x() as usize, point.y() as usize, value);
     }
 
-    pub fn inflate(&mut self, inflation_factor: usize) {
-        let mut inflated_matrix: Vec<Vec<Tile>> = Vec::with_capacity(inflation_factor * self.height);
-        for _x in 0..(inflation_factor * self.height) {
-            let mut arr_x: Vec<Tile> = Vec::with_capacity(inflation_factor * self.width);
-            for _y in 0..(inflation_factor * self.width) {
-                arr_x.push(Tile::Empty);
-            }
-            inflated_matrix.push(arr_x);
-        }
+    pub fn row(&self, x: usize) -> &[Tile] {
+        let start = self.index(x, 0);
+        return &self.tiles[start..start + self.width];
+    }
 
+    // iterate over all tiles as (x, y, tile) row by row
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, Tile)> + '_ {
+        let width = self.width;
+        return self.tiles.iter().enumerate().map(move |(i, &tile)| (i / width, i % width, tile));
+    }
+
+    pub fn inflate(&mut self, inflation_factor: usize) {
+        let inflated_width = inflation_factor * self.width;
+        let mut inflated_tiles: Vec<Tile> = Vec::with_capacity(inflation_factor * inflation_factor * self.tiles.len());
         for row in 0..self.height {
-            for column in 0..self.width {
-                for xr in 0..inflation_factor {
-                    for yr in 0..inflation_factor {
-                        inflated_matrix[row * inflation_factor + xr][column * inflation_factor + yr] = self.level[row][column];
-                    }
+            let mut inflated_row: Vec<Tile> = Vec::with_capacity(inflated_width);
+            for &tile in self.row(row) {
+                for _ in 0..inflation_factor {
+                    inflated_row.push(tile);
                 }
             }
+            for _ in 0..inflation_factor {
+                inflated_tiles.extend_from_slice(&inflated_row);
+            }
         }
-        self.level = inflated_matrix;
-        self.width = inflation_factor * self.width;
-        self.height = inflation_factor * self.height;
+        self.tiles = inflated_tiles;
+        self.width = inflated_width;
+        self.height *= inflation_factor;
     }
 
-    pub fn render_inner(&self) -> &Vec<Vec<Tile>> {
-        return &self.level;
+    pub fn render_inner(&self) -> &[Tile] {
+        return &self.tiles;
+    }
+
+    // Tile is repr(u8), so the tiles buffer can be used as the buffer of bytes
+    pub fn tiles_as_bytes(&self) -> &[u8] {
+        return unsafe { std::slice::from_raw_parts(self.tiles.as_ptr() as *const u8, self.tiles.len()) };
     }
 }
 
@@ -170,15 +620,72 @@ impl Level {
     }
 
     #[wasm_bindgen]
-    pub fn render(&self) -> Array {
-        let mut to_return: Vec<u8> = Vec::with_capacity(self.width * self.height);
-        for x in 0..self.height {
-            for y in 0..self.width {
-                let v: &Tile = &self.level[x][y];
-                to_return.push(*v as u8);
-            }
+    pub fn render(&self) -> Uint8Array {
+        return Uint8Array::from(self.tiles_as_bytes());
+    }
+
+    // copy tiles into the buffer, return the number of written tiles
+    // if the buffer is shorter than height * width, then only the first tiles are copied and the rest are silently dropped
+    // so compare the result with height * width to detect the short buffer
+    #[wasm_bindgen]
+    pub fn render_into(&self, buffer: &mut [u8]) -> usize {
+        let count = buffer.len().min(self.tiles.len());
+        buffer[..count].copy_from_slice(&self.tiles_as_bytes()[..count]);
+        return count;
+    }
+
+    #[wasm_bindgen]
+    pub fn corridor(&self, index: usize) -> Option<CorridorInfo> {
+        return self.corridors.get(index).cloned();
+    }
+
+    // 0 if rooms are not placed by the BSP
+    #[wasm_bindgen]
+    pub fn bsp_nodes_count(&self) -> usize {
+        return self.bsp_nodes.len();
+    }
+
+    #[wasm_bindgen]
+    pub fn bsp_node(&self, index: usize) -> Option<BspNodeInfo> {
+        return self.bsp_nodes.get(index).cloned();
+    }
+
+    // plain array with pairs of connected room indices
+    #[wasm_bindgen]
+    pub fn room_connections(&self) -> Uint32Array {
+        let mut to_return: Vec<u32> = Vec::with_capacity(2 * self.corridors.len());
+        for c in &self.corridors {
+            to_return.push(c.rooms.0 as u32);
+            to_return.push(c.rooms.1 as u32);
         }
-        return to_return.into_iter().map(JsValue::from).collect();
+
+        return Uint32Array::from(to_return.as_slice());
+    }
+
+    // the first room of the longest path in the graph of corridors
+    #[wasm_bindgen]
+    pub fn start_room(&self) -> Option<usize> {
+        return self.critical_path.first().copied();
+    }
+
+    // the last room of the longest path, it is the same as the start room, if the level has only one room
+    #[wasm_bindgen]
+    pub fn exit_room(&self) -> Option<usize> {
+        return self.critical_path.last().copied();
+    }
+
+    // room indices from the start room to the exit room, each two neighbouring rooms are connected by the corridor
+    #[wasm_bindgen]
+    pub fn critical_path(&self) -> Uint32Array {
+        let to_return: Vec<u32> = self.critical_path.iter().map(|&r| r as u32).collect();
+        return Uint32Array::from(to_return.as_slice());
+    }
+
+    // the view into WASM memory without copy
+    // it becomes invalid when the level is changed or dropped, or when WASM memory grows
+    #[wasm_bindgen]
+    pub fn tiles_view(&self) -> Uint8Array {
+        return unsafe { Uint8Array::view(self.tiles_as_bytes()) };
     }
 }
 
@@ -187,8 +694,8 @@ impl fmt::Display for Level{
         let mut to_return = String::new();
         for x in 0..self.height {
             let mut x_str = String::new();
-            for y in 0..self.width {
-                x_str.push_str(match self.level[x][y] {
+            for &tile in self.row(x) {
+                x_str.push_str(match tile {
                     Tile::Floor => "□ ",
                     Tile::Empty =>  "■ " ,
                     _ => "● "
@@ -1,18 +1,34 @@
 #[path = "preudo_random.rs"] mod preudo_random;
 #[path = "room_generator.rs"] mod room_generator;
 #[path = "corridor_generator.rs"] mod corridor_generator;
-
-use crate::level::options::{Options, Tile, TileMask, TilePoint, RoomType};
+#[path = "bsp.rs"] mod bsp;
+#[path = "poisson.rs"] mod poisson;
+#[path = "cave_generator.rs"] mod cave_generator;
+#[path = "tunnel_generator.rs"] mod tunnel_generator;
+#[path = "maze_generator.rs"] mod maze_generator;
+#[path = "wfc_generator.rs"] mod wfc_generator;
+#[path = "room_shape.rs"] pub mod room_shape;
+#[path = "prefab.rs"] pub mod prefab;
+#[path = "dungeon_generator.rs"] pub mod dungeon_generator;
+
+use crate::level::options::{Options, OptionsError, Tile, TileMask, TilePoint, RoomType, ConnectionStrategy, RetryPolicy, PrefabPlacement, RoomSizeRange, RoomPlacement, GenerationMode, CaveSettings, CavernMethod, TunnelSettings, MazeAlgorithm, MazeSettings, WfcSettings};
 use preudo_random::PseudoRandom;
 use room_generator::{Room, RoomGenerator};
 use crate::level::point::Point;
-use crate::level::Level;
-use corridor_generator::{generate_corridors, Corridor};
+use crate::level::{Level, RoomInfo, CorridorInfo, BspNodeInfo};
+use corridor_generator::{generate_corridors, critical_path, floor_critical_path, Corridor};
+use room_shape::{RoomShape, RoomShapes};
+use prefab::{Prefab, PrefabError};
+use cave_generator::{generate_cave, floor_regions};
+use tunnel_generator::generate_tunnels;
+use maze_generator::generate_maze;
+use wfc_generator::{generate_wfc, WfcSample};
 
 use wasm_bindgen::prelude::*;
 
 #[derive(Debug)]
 struct GridPattern {
+    #[allow(dead_code)]
     name: String,
     pattern: Vec<Vec<TileMask>>,
     paint_offsets: Vec<TilePoint>
@@ -41,10 +57,13 @@ impl GridPattern {
 pub struct LevelGenerator {
     options: Options,
     room_generator: RoomGenerator,
-    grid_patterns: Vec<GridPattern>
+    room_shapes: RoomShapes,
+    grid_patterns: Vec<GridPattern>,
+    wfc_sample: Option<WfcSample>
 }
 
 impl LevelGenerator {
+    #[allow(clippy::vec_init_then_push)]
     pub fn new_with_options(options: Options) -> LevelGenerator {
         let random = PseudoRandom::new(options.random_seed() as u64);
         let room_generator = RoomGenerator::new(random);
@@ -152,13 +171,36 @@ impl LevelGenerator {
             vec![TilePoint::new(bottom.clone(), Tile::TopRightOutsideCorner)]));
 
         return LevelGenerator{
-            options: options,
+            options,
             room_generator,
-            grid_patterns
+            room_shapes: RoomShapes::new(),
+            grid_patterns,
+            wfc_sample: None
         };
     }
 
-    fn render_rooms_on_level(&self, level: &mut Level, rooms: &Vec<Room>) {
+    // add the shape for rooms, return the room type, which should be used in options to generate rooms of this shape
+    pub fn register_room_shape(&mut self, shape: Box<dyn RoomShape>) -> RoomType {
+        return self.room_shapes.register(shape);
+    }
+
+    // add the prefab room, return the room type of it, use the index of this type in PrefabPlacement
+    pub fn register_prefab(&mut self, prefab: Prefab) -> RoomType {
+        return self.room_shapes.register_prefab(prefab);
+    }
+
+    // start the random generator again with the new seed
+    fn reseed(&mut self, random_seed: usize) {
+        self.options.set_seed(random_seed);
+        self.room_generator = RoomGenerator::new(PseudoRandom::new(random_seed as u64));
+    }
+
+    // use the generated level as the sample for the wave function collapse
+    pub fn set_wfc_sample_level(&mut self, level: &Level) {
+        self.wfc_sample = Some(WfcSample::from_level(level));
+    }
+
+    fn render_rooms_on_level(&self, level: &mut Level, rooms: &[Room]) {
         for i in 0..rooms.len() {
             let room = &rooms[i];
             let tiles = room.get_tiles();
@@ -172,7 +214,7 @@ impl LevelGenerator {
         }
     }
 
-    fn render_corridors_on_level(&self, level: &mut Level, corridors: &Vec<Corridor>) {
+    fn render_corridors_on_level(&self, level: &mut Level, corridors: &[Corridor]) {
         for i in 0..corridors.len() {
             let corridor = &corridors[i];
             let points = corridor.get_tiles();
@@ -183,7 +225,106 @@ impl LevelGenerator {
         }
     }
 
-    fn surrounding_area_matches_pattern(&self, level: &Level, position: Point, pattern: &Vec<Vec<TileMask>>) -> bool {
+    // every generation mode ends by this step, so all levels use the same tiles
+    fn inflate_and_paint_walls(&self, level: &mut Level) {
+        level.inflate(2);
+
+        let mut tile_points: Vec<TilePoint> = Vec::with_capacity(level.height() * level.width());
+        for x in 1..level.height() - 1 {
+            let x_int = x as i32;
+            for y in 1..level.width() - 1 {
+                let y_int = y as i32;
+                if level.get_from_coordinates(x, y) == Tile::Floor {
+                    for p in 0..self.grid_patterns.len() {
+                        let tile = &self.grid_patterns[p];
+                        if self.surrounding_area_matches_pattern(level, Point::new(x_int, y_int), tile.pattern()) {
+                            for o in 0..tile.paint_offsets_length() {
+                                let paint_point = tile.paint_offset(o);
+                                tile_points.push(TilePoint::new(Point::new(paint_point.position().x() + x_int, paint_point.position().y() + y_int), *paint_point.tile_type()));
+                            }
+                        }
+                    }
+                };
+            }
+        }
+
+        for p in 0..tile_points.len() {
+            let tile_point = tile_points[p];
+            level.set_tile(tile_point.position().x() as usize, tile_point.position().y() as usize, *tile_point.tile_type());
+        } 
+    }
+
+    // the level from the floor mask of modes without corridors
+    fn floor_mask_level(&self, floor: &[Vec<bool>]) -> Level {
+        let mut level = Level::new(self.options.level_height(), self.options.level_width());
+        for x in 0..floor.len() {
+            for y in 0..floor[x].len() {
+                if floor[x][y] {
+                    level.set_tile(x, y, Tile::Floor);
+                }
+            }
+        }
+        self.inflate_and_paint_walls(&mut level);
+        return level;
+    }
+
+    fn generate_caves(&mut self) -> Level {
+        let floor = generate_cave(self.room_generator.random_mut(), self.options.level_height(), self.options.level_width(), self.options.border(), self.options.cave_settings());
+        let mut level = self.floor_mask_level(&floor);
+        // caves have no corridors, so there are no failed connections, the connectivity is given by the number of regions
+        level.set_statistics(0, 0, 0, true, Vec::new(), Vec::new());
+        level.set_regions_count(floor_regions(&floor).len());
+        return level;
+    }
+
+    fn generate_tunnels(&mut self) -> Level {
+        let (floor, rooms) = generate_tunnels(self.room_generator.random_mut(), &self.options);
+        let mut level = self.floor_mask_level(&floor);
+        self.set_floor_mask_rooms(&mut level, &floor, &rooms);
+        level.set_regions_count(floor_regions(&floor).len());
+        return level;
+    }
+
+    fn generate_maze(&mut self) -> Level {
+        let maze = generate_maze(self.room_generator.random_mut(), &self.options);
+        let mut level = self.floor_mask_level(&maze.floor);
+        self.set_floor_mask_rooms(&mut level, &maze.floor, &maze.rooms);
+        level.set_regions_count(floor_regions(&maze.floor).len());
+        level.set_dead_ends_count(maze.dead_ends_count);
+        return level;
+    }
+
+    fn generate_wfc(&mut self) -> Result<Level, OptionsError> {
+        let sample = match &self.wfc_sample {
+            Some(s) => s,
+            None => return Err(OptionsError::NoWfcSample)
+        };
+        let (floor, restarts_count) = generate_wfc(self.room_generator.random_mut(), self.options.level_height(), self.options.level_width(), self.options.border(), sample, self.options.wfc_settings())?;
+        let mut level = self.floor_mask_level(&floor);
+        level.set_statistics(0, 0, 0, true, Vec::new(), Vec::new());
+        level.set_regions_count(floor_regions(&floor).len());
+        level.set_restarts_count(restarts_count);
+        return Ok(level);
+    }
+
+    // rooms of modes without corridors, all rooms are connected by the floor
+    fn set_floor_mask_rooms(&self, level: &mut Level, floor: &[Vec<bool>], rooms: &[Room]) {
+        let mut room_centers: Vec<Point> = Vec::with_capacity(rooms.len());
+        let mut room_infos: Vec<RoomInfo> = Vec::with_capacity(rooms.len());
+        for i in 0..rooms.len() {
+            let room = &rooms[i];
+            let center = Point::new(room.center().x() * 2 + 1, room.center().y() * 2 + 1);
+            room_centers.push(center.clone());
+            // rooms of these modes are carved into the floor mask, so their floor is the floor inside the bounding box
+            let room_floor: Vec<Vec<bool>> = (0..room.height()).map(|x| (0..room.width()).map(|y| floor[room.position().x() as usize + x][room.position().y() as usize + y]).collect()).collect();
+            room_infos.push(RoomInfo::new(i, Point::new(room.position().x() * 2, room.position().y() * 2), room.height() * 2, room.width() * 2, center, room.room_type(), 0, Vec::new(), room_floor));
+        }
+        level.set_rooms(room_infos);
+        level.set_statistics(rooms.len(), 0, 0, true, room_centers, Vec::new());
+        level.set_critical_path(floor_critical_path(floor, rooms));
+    }
+
+    fn surrounding_area_matches_pattern(&self, level: &Level, position: Point, pattern: &[Vec<TileMask>]) -> bool {
         let start = Point::new(position.x() - 1, position.y() - 1);
         for x in 0..pattern.len() {
             for y in 0..pattern[x].len(){
@@ -215,6 +356,7 @@ impl LevelGenerator {
 #[wasm_bindgen]
 impl LevelGenerator {
     #[wasm_bindgen(constructor)]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(level_width: usize,
                level_height: usize,
                min_room_width: usize,
@@ -242,7 +384,7 @@ impl LevelGenerator {
         if room_diamond {
             room_types.push(RoomType::Diamond);
         }
-        if room_types.len() == 0 {
+        if room_types.is_empty() {
             room_types.push(RoomType::Rectangle);
         }
 
@@ -293,72 +435,285 @@ impl LevelGenerator {
 
     #[wasm_bindgen]
     pub fn add_room_type(&mut self, room_type: u8) {
-        match room_type {
-            0 => self.options.add_room_type(RoomType::Square),
-            1 => self.options.add_room_type(RoomType::Rectangle),
-            2 => self.options.add_room_type(RoomType::Cross),
-            3 => self.options.add_room_type(RoomType::Diamond),
+        if let Some(t) = RoomType::from_id(room_type) {
+            self.options.add_room_type(t);
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn remove_room_type(&mut self, room_type: u8) {
+        if let Some(t) = RoomType::from_id(room_type) {
+            self.options.remove_room_type(t);
+        }
+    }
+
+    // add the room type, which is selected with the probability weight / (sum of all weights)
+    #[wasm_bindgen]
+    pub fn add_weighted_room_type(&mut self, room_type: u8, weight: usize) {
+        if let Some(t) = RoomType::from_id(room_type) {
+            self.options.add_room_type(t);
+            self.options.set_room_type_weight(t, weight);
+        };
+    }
+
+    #[wasm_bindgen]
+    pub fn set_room_type_weight(&mut self, room_type: u8, weight: usize) {
+        if let Some(t) = RoomType::from_id(room_type) {
+            self.options.set_room_type_weight(t, weight);
+        }
+    }
+
+    // own size range of the room type instead of the common range from set_room_size
+    #[wasm_bindgen]
+    pub fn set_room_type_size(&mut self, room_type: u8, min_room_width: usize, max_room_width: usize, min_room_height: usize, max_room_height: usize) {
+        if let Some(t) = RoomType::from_id(room_type) {
+            self.options.set_room_type_size(t, Some(RoomSizeRange::new(min_room_width, max_room_width, min_room_height, max_room_height)));
+        }
+    }
+
+    // all room types get weight 1 and the common size range
+    #[wasm_bindgen]
+    pub fn reset_room_type_settings(&mut self) {
+        self.options.reset_room_type_settings();
+    }
+
+    #[wasm_bindgen]
+    pub fn set_connection_strategy(&mut self, strategy: u8) {
+        match strategy {
+            0 => self.options.set_connection_strategy(ConnectionStrategy::Chain),
+            1 => self.options.set_connection_strategy(ConnectionStrategy::MinimumSpanningTree),
+            2 => self.options.set_connection_strategy(ConnectionStrategy::BspSiblings),
             _ => {}
         };
     }
 
     #[wasm_bindgen]
-    pub fn remove_room_type(&mut self, room_type: u8) {
-        match room_type {
-            0 => self.options.remove_room_type(RoomType::Square),
-            1 => self.options.remove_room_type(RoomType::Rectangle),
-            2 => self.options.remove_room_type(RoomType::Cross),
-            3 => self.options.remove_room_type(RoomType::Diamond),
+    pub fn set_room_placement(&mut self, placement: u8) {
+        match placement {
+            0 => self.options.set_room_placement(RoomPlacement::Random),
+            1 => self.options.set_room_placement(RoomPlacement::Bsp),
+            2 => self.options.set_room_placement(RoomPlacement::Poisson),
             _ => {}
         };
     }
 
+    // the minimal distance between room centers for the Poisson placement, 0 for automatic distance
     #[wasm_bindgen]
-    pub fn generate(&mut self) -> Level {
-        let mut level = Level::new(self.options.level_height(), self.options.level_width());
-        let mut rooms = self.room_generator.generate_rooms(&self.options);
-        let corridors = generate_corridors(&mut rooms, &self.options);
+    pub fn set_min_room_distance(&mut self, min_room_distance: usize) {
+        self.options.set_min_room_distance(min_room_distance);
+    }
 
-        self.render_rooms_on_level(&mut level, &rooms);
-        self.render_corridors_on_level(&mut level, &corridors);
+    // 0 - rooms and corridors, 1 - caves, 2 - tunnels, 3 - maze, 4 - wave function collapse
+    #[wasm_bindgen]
+    pub fn set_generation_mode(&mut self, mode: u8) {
+        match mode {
+            0 => self.options.set_generation_mode(GenerationMode::Rooms),
+            1 => self.options.set_generation_mode(GenerationMode::Caves),
+            2 => self.options.set_generation_mode(GenerationMode::Tunnels),
+            3 => self.options.set_generation_mode(GenerationMode::Maze),
+            4 => self.options.set_generation_mode(GenerationMode::Wfc),
+            _ => {}
+        };
+    }
 
-        level.inflate(2);
+    // rule has the form B5678/S45678, numbers are counts of wall neighbours, when the cell becomes (B) or stays (S) the wall
+    #[wasm_bindgen]
+    pub fn set_cave_settings(&mut self, fill_ratio: f64, rule: &str, iterations: usize, min_region_size: usize) -> Result<(), OptionsError> {
+        let (birth, survival) = CaveSettings::parse_rule(rule)?;
+        self.options.set_cave_settings(CaveSettings::new(fill_ratio, birth, survival, iterations, min_region_size));
+        return Ok(());
+    }
 
-        let mut tile_points: Vec<TilePoint> = Vec::with_capacity(level.height() * level.width());
-        for x in 1..level.height() - 1 {
-            let x_int = x as i32;
-            for y in 1..level.width() - 1 {
-                let y_int = y as i32;
-                match level.get_from_coordinates(x, y) {
-                    Tile::Floor => {
-                        for p in 0..self.grid_patterns.len() {
-                            let tile = &self.grid_patterns[p];
-                            if self.surrounding_area_matches_pattern(&level, Point::new(x_int, y_int), tile.pattern()) {
-                                for o in 0..tile.paint_offsets_length() {
-                                    let paint_point = tile.paint_offset(o);
-                                    tile_points.push(TilePoint::new(Point::new(paint_point.position().x() + x_int, paint_point.position().y() + y_int), paint_point.tile_type().clone()));
-                                }
-                            }
-                        }
-                    },
-                    _ => {}
-                };
+    #[wasm_bindgen]
+    pub fn set_tunnel_settings(&mut self, walkers: usize, turn_probability: f64, lifetime: usize, coverage: f64, room_probability: f64) {
+        self.options.set_tunnel_settings(TunnelSettings::new(walkers, turn_probability, lifetime, coverage, room_probability));
+    }
+
+    // algorithm: 0 - recursive backtracker, 1 - Prim's algorithm, 2 - Kruskal's algorithm
+    #[wasm_bindgen]
+    pub fn set_maze_settings(&mut self, algorithm: u8, braid_factor: f64, room_attempts: usize) {
+        let maze_algorithm = match algorithm {
+            1 => MazeAlgorithm::Prim,
+            2 => MazeAlgorithm::Kruskal,
+            _ => MazeAlgorithm::Backtracker
+        };
+        self.options.set_maze_settings(MazeSettings::new(maze_algorithm, braid_factor, room_attempts));
+    }
+
+    #[wasm_bindgen]
+    pub fn set_wfc_settings(&mut self, pattern_size: usize, symmetry: bool, max_restarts: usize) {
+        self.options.set_wfc_settings(WfcSettings::new(pattern_size, symmetry, max_restarts));
+    }
+
+    // parse the ascii sample for the wave function collapse, '.' is the floor, all other characters are walls
+    #[wasm_bindgen]
+    pub fn set_wfc_sample(&mut self, sample: &str) -> Result<(), OptionsError> {
+        self.wfc_sample = Some(WfcSample::parse(sample)?);
+        return Ok(());
+    }
+
+    // method: 0 - cellular automaton, 1 - drunkard's walk
+    #[wasm_bindgen]
+    pub fn set_caverns(&mut self, cavern_ratio: f64, method: u8) {
+        match method {
+            0 => self.options.set_caverns(cavern_ratio, CavernMethod::CellularAutomata),
+            1 => self.options.set_caverns(cavern_ratio, CavernMethod::DrunkardWalk),
+            _ => {}
+        };
+    }
+
+    // how many times random placement retries rooms, which can not be placed, 0 stops the placement at the first failure
+    #[wasm_bindgen]
+    pub fn set_placement_attempts(&mut self, placement_attempts: usize) {
+        self.options.set_placement_attempts(placement_attempts);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_loop_ratio(&mut self, loop_ratio: f64) {
+        self.options.set_loop_ratio(loop_ratio);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_search_limit(&mut self, search_limit: usize) {
+        self.options.set_search_limit(search_limit);
+    }
+
+    #[wasm_bindgen]
+    pub fn set_retry_policy(&mut self, limit_raises: usize, partner_attempts: usize, relax_room_borders: bool) {
+        self.options.set_retry_policy(RetryPolicy::new(limit_raises, partner_attempts, relax_room_borders));
+    }
+
+    // parse the ascii template and add the prefab room, return the index of the prefab
+    #[wasm_bindgen(js_name = register_prefab)]
+    pub fn register_prefab_template(&mut self, template: &str) -> Result<usize, PrefabError> {
+        let prefab = Prefab::parse(template)?;
+        return match self.register_prefab(prefab) {
+            RoomType::Prefab(index) => Ok(index),
+            _ => unreachable!()
+        };
+    }
+
+    #[wasm_bindgen]
+    pub fn add_prefab(&mut self, prefab: usize, min_count: usize, max_count: usize, allow_rotation: bool) {
+        self.options.add_prefab(PrefabPlacement::new(prefab, min_count, max_count, allow_rotation));
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_prefabs(&mut self) {
+        self.options.set_prefabs(Vec::new());
+    }
+
+    // errors are mostly from invalid options, but some of them depend on the generation itself
+    // (PrefabNotPlaced, WfcContradiction), so the generation can fail with valid options and succeed with the other seed
+    #[wasm_bindgen]
+    pub fn generate(&mut self) -> Result<Level, OptionsError> {
+        self.options.validate()?;
+        match self.options.generation_mode() {
+            GenerationMode::Caves => return Ok(self.generate_caves()),
+            GenerationMode::Tunnels => return Ok(self.generate_tunnels()),
+            GenerationMode::Maze => return Ok(self.generate_maze()),
+            GenerationMode::Wfc => return self.generate_wfc(),
+            GenerationMode::Rooms => {}
+        };
+        for &room_type in self.options.room_types() {
+            match room_type {
+                RoomType::Custom(index) if !self.room_shapes.contains(room_type) => return Err(OptionsError::UnknownRoomShape { index }),
+                _ => {}
+            }
+        }
+        for placement in self.options.prefabs() {
+            if !self.room_shapes.contains(RoomType::Prefab(placement.prefab())) {
+                return Err(OptionsError::UnknownPrefab { index: placement.prefab() });
             }
         }
 
-        for p in 0..tile_points.len() {
-            let tile_point = tile_points[p];
-            level.set_tile(tile_point.position().x() as usize, tile_point.position().y() as usize, tile_point.tile_type().clone());
-        } 
+        let mut level = Level::new(self.options.level_height(), self.options.level_width());
+        let (mut rooms, bsp_tree) = self.room_generator.generate_rooms(&self.options, &self.room_shapes)?;
+        let (corridors, failed_connections) = generate_corridors(&mut rooms, &self.options, bsp_tree.as_ref());
+
+        self.render_rooms_on_level(&mut level, &rooms);
+        self.render_corridors_on_level(&mut level, &corridors);
+
+        self.inflate_and_paint_walls(&mut level);
 
         let mut room_centers: Vec<Point> = Vec::with_capacity(rooms.len());
+        let mut room_infos: Vec<RoomInfo> = Vec::with_capacity(rooms.len());
         for i in 0..rooms.len() {
-            let r_center = rooms[i].center();
-            room_centers.push(Point::new(r_center.x() * 2 + 1, r_center.y() * 2 + 1));
+            let room = &rooms[i];
+            let r_center = room.center();
+            let center = Point::new(r_center.x() * 2 + 1, r_center.y() * 2 + 1);
+            room_centers.push(center);
+            let doors: Vec<Point> = room.doors().iter().map(|d| Point::new(d.x() * 2 + 1, d.y() * 2 + 1)).collect();
+            room_infos.push(RoomInfo::new(i, Point::new(room.position().x() * 2, room.position().y() * 2), room.height() * 2, room.width() * 2, center, room.room_type(), room.orientation(), doors, room.floor_mask()));
+        }
+        level.set_rooms(room_infos);
+        let placed_rooms_count = rooms.iter().filter(|r| !matches!(r.room_type(), RoomType::Prefab(_))).count();
+        level.set_requested_rooms(self.options.number_of_rooms(), placed_rooms_count);
+
+        let mut corridor_infos: Vec<CorridorInfo> = Vec::with_capacity(corridors.len());
+        for i in 0..corridors.len() {
+            let corridor = &corridors[i];
+            let points: Vec<Point> = corridor.get_tiles().iter().map(|p| Point::new(p.x() * 2 + 1, p.y() * 2 + 1)).collect();
+            corridor_infos.push(CorridorInfo::new(i, corridor.rooms(), points, corridor.is_loop()));
+        }
+        level.set_corridors(corridor_infos);
+        level.set_critical_path(critical_path(rooms.len(), &corridors));
+
+        if let Some(tree) = &bsp_tree {
+            // rooms are reordered by the corridor generator, so find the room of each leaf after it
+            let mut leaf_rooms: Vec<Option<usize>> = vec![None; tree.nodes().len()];
+            for i in 0..rooms.len() {
+                if let Some(leaf) = rooms[i].leaf() {
+                    leaf_rooms[leaf] = Some(i);
+                }
+            }
+            let nodes: Vec<BspNodeInfo> = tree.nodes().iter().enumerate().map(|(i, node)| {
+                return BspNodeInfo::new(i, Point::new(node.position().x() * 2, node.position().y() * 2), node.height() * 2, node.width() * 2, node.parent(), node.children(), leaf_rooms[i]);
+            }).collect();
+            level.set_bsp_nodes(nodes);
         }
 
-        level.set_statistics(rooms.len(), corridors.len(), corridors.len() + 1 == rooms.len(), room_centers);
+        let loop_corridors_count = corridors.iter().filter(|c| c.is_loop()).count();
+        let all_corridors = failed_connections.is_empty();
+        level.set_statistics(rooms.len(), corridors.len(), loop_corridors_count, all_corridors, room_centers, failed_connections);
 
-        return level;
+        return Ok(level);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::options::{OptionsBuilder, PathFinderTile};
+    use corridor_generator::generate_pathing_grid;
+
+    #[test]
+    fn loop_corridors_keep_room_borders_after_relaxed_retry() {
+        let mut relaxed_levels: usize = 0;
+        for seed in 1..=40 {
+            let options = OptionsBuilder::new()
+                .random_seed(seed)
+                .level_width(40)
+                .level_height(40)
+                .number_of_rooms(30)
+                .border(2)
+                .room_border(2)
+                .loop_ratio(1.0)
+                .retry_policy(RetryPolicy::new(0, 0, true))
+                .build()
+                .unwrap();
+            let mut generator = LevelGenerator::new_with_options(options);
+            let (mut rooms, bsp_tree) = generator.room_generator.generate_rooms(&generator.options, &generator.room_shapes).unwrap();
+            let bordered_grid = generate_pathing_grid(&rooms, &generator.options, generator.options.room_border());
+            let (corridors, _) = generate_corridors(&mut rooms, &generator.options, bsp_tree.as_ref());
+            let is_bordered = |c: &Corridor| c.get_tiles().iter().all(|p| matches!(bordered_grid[p.x() as usize][p.y() as usize], PathFinderTile::Pathable));
+            if corridors.iter().any(|c| !c.is_loop() && !is_bordered(c)) {
+                relaxed_levels += 1;
+            }
+            for corridor in corridors.iter().filter(|c| c.is_loop()) {
+                assert!(is_bordered(corridor), "seed {} loop between rooms {:?}", seed, corridor.rooms());
+            }
+        }
+        assert!(relaxed_levels > 0);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,575 @@
+use promethean_wasm::level::Level;
+use promethean_wasm::level::options::{CaveSettings, CavernMethod, ConnectionStrategy, GenerationMode, Options, OptionsBuilder, PrefabPlacement, RetryPolicy, RoomPlacement, RoomSizeRange, RoomType, Tile, TunnelSettings, MazeAlgorithm, MazeSettings, WfcSettings};
+use promethean_wasm::level_generator::LevelGenerator;
+use promethean_wasm::level_generator::prefab::Prefab;
+use promethean_wasm::level_generator::dungeon_generator::{Dungeon, DungeonGenerator};
+
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "Usage: promethean_bin [OPTIONS]
+
+Generate a dungeon level and print it to stdout or write it into a file.
+
+Options:
+  --mode <MODE>             what kind of level is generated: rooms, caves, tunnels, maze or wfc (default rooms)
+  --width <N>               level width (default 64)
+  --height <N>              level height (default 64)
+  --min-room-width <N>      minimal room width (default 5)
+  --max-room-width <N>      maximal room width (default 7)
+  --min-room-height <N>     minimal room height (default 5)
+  --max-room-height <N>     maximal room height (default 7)
+  --rooms <N>               target number of rooms (default 45)
+  --seed <N>                random seed (default 1)
+  --border <N>              the size of the level border (default 1)
+  --room-border <N>         the size of each room's border (default 1)
+  --overlap                 allow rooms to overlap each other
+  --room-types <LIST>       comma separated list of square, rectangle, cross, diamond, circle, ellipse, ring,
+                            l-shape, t-shape, u-shape, offset-plus, each type can have the weight after colon,
+                            for example rectangle:70,diamond:5 (default square,rectangle,cross,diamond)
+  --room-size <SPEC>        own size range of the room type, SPEC is TYPE,MIN_WIDTH,MAX_WIDTH,MIN_HEIGHT,MAX_HEIGHT,
+                            can be used several times
+  --placement <MODE>        how rooms are placed: random, bsp (binary space partition) or poisson (evenly spread
+                            rooms) (default random)
+  --min-distance <N>        the minimal distance between room centers for poisson placement (default 0 - automatic)
+  --attempts <N>            how many times random placement retries rooms, which can not be placed, by shrinking,
+                            changing the type or moving the room (default 100, 0 - stop at the first failure)
+  --connection <STRATEGY>   how rooms are connected: mst (minimum spanning tree), chain or bsp (siblings
+                            of the BSP tree, requires bsp placement) (default mst)
+  --loops <RATIO>           fraction of the remaining shortest room connections added as loop corridors,
+                            from 0.0 to 1.0 (default 0.0)
+  --prefab <SPEC>           add prefab room from the ascii template file, SPEC is FILE[,MIN[,MAX[,fixed]]],
+                            MIN and MAX are the number of copies (default 1), fixed forbids rotation,
+                            can be used several times
+  --cave-fill <RATIO>       the fraction of walls in the initial random cave, from 0.0 to 1.0 (default 0.45)
+  --cave-rule <RULE>        birth and survival counts of wall neighbours for cave walls (default B5678/S45678)
+  --cave-iterations <N>     the number of cellular automaton steps for caves (default 5)
+  --cave-min-region <N>     cave regions with less tiles are filled by walls (default 16)
+  --tunnels <SPEC>          random walkers for tunnels, SPEC is WALKERS,TURN_PROBABILITY,LIFETIME,COVERAGE,ROOM_PROBABILITY
+                            (default 4,0.15,100,0.3,0.1), room sizes are from the common room size range
+  --maze <SPEC>             maze parameters, SPEC is ALGORITHM[,BRAID[,ROOM_ATTEMPTS]], ALGORITHM is backtracker, prim
+                            or kruskal, BRAID is the probability to remove each dead end (default backtracker,0.0,0),
+                            room sizes are from the common room size range
+  --wfc-sample <FILE>       the ascii sample for wfc mode, '.' is the floor, all other characters are walls,
+                            the ascii output of the generator can be used
+  --wfc <SPEC>              wave function collapse parameters, SPEC is PATTERN_SIZE[,SYMMETRY[,MAX_RESTARTS]],
+                            SYMMETRY adds rotated and mirrored patterns (default 3,true,10)
+  --caverns <RATIO>         the fraction of rooms, which are turned into caverns, from 0.0 to 1.0 (default 0.0)
+  --cavern-method <METHOD>  how the cavern floor is grown: automaton or walk (default automaton)
+  --floors <N>              the number of dungeon floors, neighbouring floors are connected by stairs at the same
+                            coordinates, each floor has its own seed derived from --seed (default 1)
+  --search-limit <N>        the maximum number of path finder steps for one corridor (default 2000)
+  --limit-raises <N>        how many times the search limit is doubled for a failed corridor (default 2)
+  --partner-attempts <N>    how many other room pairs are used to connect disconnected rooms (default 64)
+  --relax-borders <BOOL>    ignore room borders when connecting disconnected rooms (default true)
+  --format <FORMAT>         output format: ascii, csv or json (default ascii)
+  --output <PATH>           write the level into the file instead of stdout
+  --help                    print this message";
+
+// options which expect a value after them
+const VALUE_KEYS: [&str; 37] = ["--mode", "--width", "--height", "--min-room-width", "--max-room-width", "--min-room-height", "--max-room-height",
+                                "--rooms", "--seed", "--border", "--room-border", "--room-types", "--room-size", "--placement", "--min-distance", "--attempts", "--connection", "--loops", "--prefab",
+                                "--cave-fill", "--cave-rule", "--cave-iterations", "--cave-min-region", "--tunnels", "--maze", "--wfc-sample", "--wfc", "--caverns", "--cavern-method",
+                                "--floors", "--search-limit", "--limit-raises", "--partner-attempts", "--relax-borders", "--format", "--output", "-o"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Ascii,
+    Csv,
+    Json
+}
+
+#[derive(Debug)]
+struct Arguments {
+    builder: OptionsBuilder,
+    prefabs: Vec<Prefab>,
+    wfc_sample: Option<String>,
+    floors_count: usize,
+    format: OutputFormat,
+    output: Option<String>
+}
+
+fn parse_number(key: &str, value: &str) -> Result<usize, String> {
+    return value.parse::<usize>().map_err(|_| format!("invalid value '{}' for {}, expected a non-negative integer", value, key));
+}
+
+fn parse_ratio(key: &str, value: &str) -> Result<f64, String> {
+    return value.parse::<f64>().map_err(|_| format!("invalid value '{}' for {}, expected a number", value, key));
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    return match value.to_lowercase().as_str() {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => Err(format!("invalid value '{}' for {}, expected true or false", value, key))
+    };
+}
+
+fn parse_room_type(name: &str) -> Result<RoomType, String> {
+    return match name.trim().to_lowercase().as_str() {
+        "square" => Ok(RoomType::Square),
+        "rectangle" | "rect" => Ok(RoomType::Rectangle),
+        "cross" => Ok(RoomType::Cross),
+        "diamond" => Ok(RoomType::Diamond),
+        "circle" => Ok(RoomType::Circle),
+        "ellipse" => Ok(RoomType::Ellipse),
+        "ring" => Ok(RoomType::Ring),
+        "l" | "l-shape" => Ok(RoomType::LShape),
+        "t" | "t-shape" => Ok(RoomType::TShape),
+        "u" | "u-shape" => Ok(RoomType::UShape),
+        "offset-plus" => Ok(RoomType::OffsetPlus),
+        _ => Err(format!("unknown room type '{}'", name))
+    };
+}
+
+fn parse_connection_strategy(name: &str) -> Result<ConnectionStrategy, String> {
+    return match name.to_lowercase().as_str() {
+        "chain" => Ok(ConnectionStrategy::Chain),
+        "mst" | "tree" => Ok(ConnectionStrategy::MinimumSpanningTree),
+        "bsp" | "siblings" => Ok(ConnectionStrategy::BspSiblings),
+        _ => Err(format!("unknown connection strategy '{}'", name))
+    };
+}
+
+fn parse_generation_mode(name: &str) -> Result<GenerationMode, String> {
+    return match name.to_lowercase().as_str() {
+        "rooms" => Ok(GenerationMode::Rooms),
+        "caves" | "cave" => Ok(GenerationMode::Caves),
+        "tunnels" | "tunnel" => Ok(GenerationMode::Tunnels),
+        "maze" => Ok(GenerationMode::Maze),
+        "wfc" => Ok(GenerationMode::Wfc),
+        _ => Err(format!("unknown generation mode '{}'", name))
+    };
+}
+
+fn parse_maze_algorithm(name: &str) -> Result<MazeAlgorithm, String> {
+    return match name.trim().to_lowercase().as_str() {
+        "backtracker" | "dfs" => Ok(MazeAlgorithm::Backtracker),
+        "prim" => Ok(MazeAlgorithm::Prim),
+        "kruskal" => Ok(MazeAlgorithm::Kruskal),
+        _ => Err(format!("unknown maze algorithm '{}'", name))
+    };
+}
+
+fn parse_cavern_method(name: &str) -> Result<CavernMethod, String> {
+    return match name.to_lowercase().as_str() {
+        "automaton" | "ca" => Ok(CavernMethod::CellularAutomata),
+        "walk" | "drunkard" => Ok(CavernMethod::DrunkardWalk),
+        _ => Err(format!("unknown cavern method '{}'", name))
+    };
+}
+
+fn parse_room_placement(name: &str) -> Result<RoomPlacement, String> {
+    return match name.to_lowercase().as_str() {
+        "random" => Ok(RoomPlacement::Random),
+        "bsp" => Ok(RoomPlacement::Bsp),
+        "poisson" => Ok(RoomPlacement::Poisson),
+        _ => Err(format!("unknown room placement '{}'", name))
+    };
+}
+
+// "null" for None, used in json output
+fn optional_to_string<T: ToString>(value: Option<T>) -> String {
+    return match value {
+        Some(v) => v.to_string(),
+        None => String::from("null")
+    };
+}
+
+fn parse_format(name: &str) -> Result<OutputFormat, String> {
+    return match name.to_lowercase().as_str() {
+        "ascii" | "txt" => Ok(OutputFormat::Ascii),
+        "csv" => Ok(OutputFormat::Csv),
+        "json" => Ok(OutputFormat::Json),
+        _ => Err(format!("unknown output format '{}'", name))
+    };
+}
+
+// read the prefab template and its placement, the index of the prefab is the order of --prefab options
+fn parse_prefab(value: &str, index: usize) -> Result<(Prefab, PrefabPlacement), String> {
+    let parts: Vec<&str> = value.split(',').collect();
+    let path = parts[0];
+    let template = fs::read_to_string(path).map_err(|e| format!("can not read prefab '{}': {}", path, e))?;
+    let prefab = Prefab::parse(&template).map_err(|e| format!("invalid prefab '{}': {}", path, e))?;
+    let min_count = match parts.get(1) {
+        Some(v) => parse_number("--prefab", v)?,
+        None => 1
+    };
+    let max_count = match parts.get(2) {
+        Some(v) => parse_number("--prefab", v)?,
+        None => min_count.max(1)
+    };
+    let allow_rotation = match parts.get(3) {
+        Some(&"fixed") => false,
+        Some(&"rotate") | None => true,
+        Some(v) => return Err(format!("invalid value '{}' for --prefab, expected fixed or rotate", v))
+    };
+    return Ok((prefab, PrefabPlacement::new(index, min_count, max_count, allow_rotation)));
+}
+
+fn parse_arguments(args: &[String]) -> Result<Option<Arguments>, String> {
+    let mut builder = OptionsBuilder::new();
+    let mut prefabs: Vec<Prefab> = Vec::new();
+    let mut wfc_sample: Option<String> = None;
+    let mut floors_count: usize = 1;
+    let mut format = OutputFormat::Ascii;
+    let mut output: Option<String> = None;
+    let default_policy = RetryPolicy::new_default();
+    let mut retry = (default_policy.limit_raises(), default_policy.partner_attempts(), default_policy.relax_room_borders());
+    let default_cave = CaveSettings::new_default();
+    let mut caverns = (0.0, CavernMethod::CellularAutomata);
+    let mut cave = (default_cave.fill_ratio(), default_cave.birth(), default_cave.survival(), default_cave.iterations(), default_cave.min_region_size());
+
+    let mut index = 0;
+    while index < args.len() {
+        let arg = &args[index];
+        index += 1;
+
+        // support both "--key value" and "--key=value"
+        let (key, inline_value) = match arg.split_once('=') {
+            Some((k, v)) => (k.to_string(), Some(v.to_string())),
+            None => (arg.clone(), None)
+        };
+
+        match key.as_str() {
+            "--help" | "-h" => return Ok(None),
+            "--overlap" => {
+                builder = builder.overlap_rooms(true);
+                continue;
+            },
+            _ => {}
+        }
+
+        if !VALUE_KEYS.contains(&key.as_str()) {
+            return Err(format!("unknown option '{}'", key));
+        }
+
+        let value = match inline_value {
+            Some(v) => v,
+            None => {
+                if index >= args.len() {
+                    return Err(format!("missing value for {}", key));
+                }
+                index += 1;
+                args[index - 1].clone()
+            }
+        };
+
+        builder = match key.as_str() {
+            "--mode" => builder.generation_mode(parse_generation_mode(&value)?),
+            "--width" => builder.level_width(parse_number(&key, &value)?),
+            "--height" => builder.level_height(parse_number(&key, &value)?),
+            "--min-room-width" => builder.min_room_width(parse_number(&key, &value)?),
+            "--max-room-width" => builder.max_room_width(parse_number(&key, &value)?),
+            "--min-room-height" => builder.min_room_height(parse_number(&key, &value)?),
+            "--max-room-height" => builder.max_room_height(parse_number(&key, &value)?),
+            "--rooms" => builder.number_of_rooms(parse_number(&key, &value)?),
+            "--seed" => builder.random_seed(parse_number(&key, &value)?),
+            "--border" => builder.border(parse_number(&key, &value)?),
+            "--room-border" => builder.room_border(parse_number(&key, &value)?),
+            "--room-types" => {
+                let mut room_types: Vec<RoomType> = Vec::new();
+                let mut weights: Vec<(RoomType, usize)> = Vec::new();
+                for item in value.split(',') {
+                    let (name, weight) = match item.split_once(':') {
+                        Some((n, w)) => (n, Some(parse_number(&key, w)?)),
+                        None => (item, None)
+                    };
+                    let room_type = parse_room_type(name)?;
+                    if !room_types.contains(&room_type) {
+                        room_types.push(room_type);
+                    }
+                    if let Some(w) = weight {
+                        weights.push((room_type, w));
+                    }
+                }
+                builder = builder.room_types(room_types);
+                for (room_type, weight) in weights {
+                    builder = builder.room_type_weight(room_type, weight);
+                }
+                builder
+            },
+            "--room-size" => {
+                let parts: Vec<&str> = value.split(',').collect();
+                if parts.len() != 5 {
+                    return Err(format!("invalid value '{}' for {}, expected TYPE,MIN_WIDTH,MAX_WIDTH,MIN_HEIGHT,MAX_HEIGHT", value, key));
+                }
+                let room_type = parse_room_type(parts[0])?;
+                let size = RoomSizeRange::new(parse_number(&key, parts[1])?, parse_number(&key, parts[2])?, parse_number(&key, parts[3])?, parse_number(&key, parts[4])?);
+                builder.room_type_size(room_type, size)
+            },
+            "--placement" => builder.room_placement(parse_room_placement(&value)?),
+            "--min-distance" => builder.min_room_distance(parse_number(&key, &value)?),
+            "--attempts" => builder.placement_attempts(parse_number(&key, &value)?),
+            "--connection" => builder.connection_strategy(parse_connection_strategy(&value)?),
+            "--loops" => builder.loop_ratio(parse_ratio(&key, &value)?),
+            "--prefab" => {
+                let (prefab, placement) = parse_prefab(&value, prefabs.len())?;
+                prefabs.push(prefab);
+                builder.prefab(placement)
+            },
+            "--cave-fill" => {
+                cave.0 = parse_ratio(&key, &value)?;
+                builder
+            },
+            "--cave-rule" => {
+                (cave.1, cave.2) = CaveSettings::parse_rule(&value).map_err(|e| e.to_string())?;
+                builder
+            },
+            "--cave-iterations" => {
+                cave.3 = parse_number(&key, &value)?;
+                builder
+            },
+            "--cave-min-region" => {
+                cave.4 = parse_number(&key, &value)?;
+                builder
+            },
+            "--tunnels" => {
+                let parts: Vec<&str> = value.split(',').collect();
+                if parts.len() != 5 {
+                    return Err(format!("invalid value '{}' for {}, expected WALKERS,TURN_PROBABILITY,LIFETIME,COVERAGE,ROOM_PROBABILITY", value, key));
+                }
+                builder.tunnel_settings(TunnelSettings::new(parse_number(&key, parts[0])?, parse_ratio(&key, parts[1])?, parse_number(&key, parts[2])?, parse_ratio(&key, parts[3])?, parse_ratio(&key, parts[4])?))
+            },
+            "--maze" => {
+                let parts: Vec<&str> = value.split(',').collect();
+                if parts.len() > 3 {
+                    return Err(format!("invalid value '{}' for {}, expected ALGORITHM[,BRAID[,ROOM_ATTEMPTS]]", value, key));
+                }
+                let braid_factor = if parts.len() > 1 { parse_ratio(&key, parts[1])? } else { 0.0 };
+                let room_attempts = if parts.len() > 2 { parse_number(&key, parts[2])? } else { 0 };
+                builder.maze_settings(MazeSettings::new(parse_maze_algorithm(parts[0])?, braid_factor, room_attempts))
+            },
+            "--wfc-sample" => {
+                wfc_sample = Some(fs::read_to_string(&value).map_err(|e| format!("can not read wfc sample '{}': {}", value, e))?);
+                builder
+            },
+            "--wfc" => {
+                let parts: Vec<&str> = value.split(',').collect();
+                if parts.len() > 3 {
+                    return Err(format!("invalid value '{}' for {}, expected PATTERN_SIZE[,SYMMETRY[,MAX_RESTARTS]]", value, key));
+                }
+                let symmetry = if parts.len() > 1 { parse_bool(&key, parts[1])? } else { true };
+                let max_restarts = if parts.len() > 2 { parse_number(&key, parts[2])? } else { 10 };
+                builder.wfc_settings(WfcSettings::new(parse_number(&key, parts[0])?, symmetry, max_restarts))
+            },
+            "--caverns" => {
+                caverns.0 = parse_ratio(&key, &value)?;
+                builder
+            },
+            "--cavern-method" => {
+                caverns.1 = parse_cavern_method(&value)?;
+                builder
+            },
+            "--floors" => {
+                floors_count = parse_number(&key, &value)?;
+                builder
+            },
+            "--search-limit" => builder.search_limit(parse_number(&key, &value)?),
+            "--limit-raises" => {
+                retry.0 = parse_number(&key, &value)?;
+                builder
+            },
+            "--partner-attempts" => {
+                retry.1 = parse_number(&key, &value)?;
+                builder
+            },
+            "--relax-borders" => {
+                retry.2 = parse_bool(&key, &value)?;
+                builder
+            },
+            "--format" => {
+                format = parse_format(&value)?;
+                builder
+            },
+            "--output" | "-o" => {
+                output = Some(value);
+                builder
+            },
+            _ => return Err(format!("unknown option '{}'", key))
+        };
+    }
+
+    builder = builder.retry_policy(RetryPolicy::new(retry.0, retry.1, retry.2));
+    builder = builder.caverns(caverns.0, caverns.1);
+    builder = builder.cave_settings(CaveSettings::new(cave.0, cave.1, cave.2, cave.3, cave.4));
+    return Ok(Some(Arguments { builder, prefabs, wfc_sample, floors_count, format, output }));
+}
+
+fn tile_to_char(tile: Tile) -> char {
+    return match tile {
+        Tile::Floor => '.',
+        Tile::Empty => ' ',
+        Tile::TopWall | Tile::BottomWall => '-',
+        Tile::LeftWall | Tile::RightWall => '|',
+        Tile::StairsDown => '>',
+        Tile::StairsUp => '<',
+        _ => '+'
+    };
+}
+
+fn render_ascii(level: &Level) -> String {
+    let mut to_return = String::with_capacity((level.width() + 1) * level.height());
+    for x in 0..level.height() {
+        for &tile in level.row(x) {
+            to_return.push(tile_to_char(tile));
+        }
+        to_return.push('\n');
+    }
+    return to_return;
+}
+
+fn render_csv(level: &Level) -> String {
+    let mut to_return = String::with_capacity(3 * level.width() * level.height());
+    for x in 0..level.height() {
+        let row: Vec<String> = level.row(x).iter().map(|&t| (t as u8).to_string()).collect();
+        to_return.push_str(&row.join(","));
+        to_return.push('\n');
+    }
+    return to_return;
+}
+
+fn render_json(level: &Level) -> String {
+    let statistics = level.statistics();
+    let centers: Vec<String> = statistics.room_centers_inner().iter().map(|p| format!("[{}, {}]", p.x(), p.y())).collect();
+    let rooms: Vec<String> = statistics.rooms_inner().iter().map(|r| {
+        let doors: Vec<String> = r.doors_inner().iter().map(|p| format!("[{}, {}]", p.x(), p.y())).collect();
+        return format!("      {{\"index\": {}, \"x\": {}, \"y\": {}, \"width\": {}, \"height\": {}, \"type\": \"{:?}\", \"orientation\": {}, \"doors\": [{}]}}", r.index(), r.x(), r.y(), r.width(), r.height(), r.room_type(), r.orientation(), doors.join(", "));
+    }).collect();
+    let failed: Vec<String> = statistics.failed_connections_inner().iter().map(|(a, b)| format!("[{}, {}]", a, b)).collect();
+    let rows: Vec<String> = (0..level.height()).map(|x| {
+        let row: Vec<String> = level.row(x).iter().map(|&t| (t as u8).to_string()).collect();
+        return format!("    [{}]", row.join(", "));
+    }).collect();
+
+    let corridors: Vec<String> = level.corridors_inner().iter().map(|c| {
+        let points: Vec<String> = c.points_inner().iter().map(|p| format!("[{}, {}]", p.x(), p.y())).collect();
+        return format!("    {{\"rooms\": [{}, {}], \"is_loop\": {}, \"points\": [{}]}}", c.rooms().0, c.rooms().1, c.is_loop(), points.join(", "));
+    }).collect();
+
+    let critical_path: Vec<String> = level.critical_path_inner().iter().map(|r| r.to_string()).collect();
+
+    let bsp_nodes: Vec<String> = level.bsp_nodes_inner().iter().map(|n| {
+        let children = n.children().map(|(a, b)| format!("[{}, {}]", a, b));
+        return format!("    {{\"index\": {}, \"x\": {}, \"y\": {}, \"width\": {}, \"height\": {}, \"parent\": {}, \"children\": {}, \"room\": {}}}",
+                       n.index(), n.x(), n.y(), n.width(), n.height(), optional_to_string(n.parent()), optional_to_string(children), optional_to_string(n.room()));
+    }).collect();
+
+    let mut to_return = String::new();
+    to_return.push_str("{\n");
+    to_return.push_str(&format!("  \"width\": {},\n", level.width()));
+    to_return.push_str(&format!("  \"height\": {},\n", level.height()));
+    to_return.push_str("  \"statistics\": {\n");
+    to_return.push_str(&format!("    \"rooms_count\": {},\n", statistics.rooms_count()));
+    to_return.push_str(&format!("    \"requested_rooms_count\": {},\n", statistics.requested_rooms_count()));
+    to_return.push_str(&format!("    \"rooms_shortfall\": {},\n", statistics.rooms_shortfall()));
+    to_return.push_str(&format!("    \"regions_count\": {},\n", statistics.regions_count()));
+    to_return.push_str(&format!("    \"dead_ends_count\": {},\n", statistics.dead_ends_count()));
+    to_return.push_str(&format!("    \"restarts_count\": {},\n", statistics.restarts_count()));
+    to_return.push_str(&format!("    \"corridors_count\": {},\n", statistics.corridors_count()));
+    to_return.push_str(&format!("    \"loop_corridors_count\": {},\n", statistics.loop_corridors_count()));
+    to_return.push_str(&format!("    \"all_corridors\": {},\n", statistics.all_corridors()));
+    to_return.push_str(&format!("    \"room_centers\": [{}],\n", centers.join(", ")));
+    to_return.push_str(&format!("    \"failed_connections\": [{}],\n", failed.join(", ")));
+    to_return.push_str(&format!("    \"rooms\": [\n{}\n    ]\n", rooms.join(",\n")));
+    to_return.push_str("  },\n");
+    to_return.push_str(&format!("  \"start_room\": {},\n", optional_to_string(level.start_room())));
+    to_return.push_str(&format!("  \"exit_room\": {},\n", optional_to_string(level.exit_room())));
+    to_return.push_str(&format!("  \"critical_path\": [{}],\n", critical_path.join(", ")));
+    to_return.push_str(&format!("  \"corridors\": [\n{}\n  ],\n", corridors.join(",\n")));
+    to_return.push_str(&format!("  \"bsp_nodes\": [\n{}\n  ],\n", bsp_nodes.join(",\n")));
+    to_return.push_str(&format!("  \"tiles\": [\n{}\n  ]\n", rows.join(",\n")));
+    to_return.push_str("}\n");
+    return to_return;
+}
+
+fn render_level(level: &Level, format: OutputFormat) -> String {
+    return match format {
+        OutputFormat::Ascii => render_ascii(level),
+        OutputFormat::Csv => render_csv(level),
+        OutputFormat::Json => render_json(level)
+    };
+}
+
+// ascii and csv floors are separated by the empty line
+fn render_dungeon(dungeon: &Dungeon, format: OutputFormat) -> String {
+    let floors: Vec<String> = dungeon.floors_inner().iter().map(|l| render_level(l, format)).collect();
+    if format != OutputFormat::Json {
+        return floors.join("\n");
+    }
+
+    let seeds: Vec<String> = dungeon.seeds_inner().iter().map(|s| s.to_string()).collect();
+    let stairs: Vec<String> = dungeon.stairs_inner().iter().map(|p| format!("[{}, {}]", p.x(), p.y())).collect();
+    let mut to_return = String::new();
+    to_return.push_str("{\n");
+    to_return.push_str(&format!("  \"seeds\": [{}],\n", seeds.join(", ")));
+    to_return.push_str(&format!("  \"stairs\": [{}],\n", stairs.join(", ")));
+    to_return.push_str(&format!("  \"floors\": [\n{}  ]\n", floors.join(",\n")));
+    to_return.push_str("}\n");
+    return to_return;
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let arguments = match parse_arguments(&args) {
+        Ok(Some(a)) => a,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        },
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let options: Options = match arguments.builder.build() {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(2);
+        }
+    };
+
+    let mut generator = LevelGenerator::new_with_options(options);
+    for prefab in arguments.prefabs {
+        generator.register_prefab(prefab);
+    }
+    if let Some(sample) = arguments.wfc_sample {
+        if let Err(e) = generator.set_wfc_sample(&sample) {
+            eprintln!("error: {}", e);
+            process::exit(2);
+        }
+    }
+
+    let text = if arguments.floors_count == 1 {
+        match generator.generate() {
+            Ok(l) => render_level(&l, arguments.format),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(2);
+            }
+        }
+    }
+    else {
+        let mut dungeon_generator = DungeonGenerator::new(generator, arguments.floors_count);
+        match dungeon_generator.generate() {
+            Ok(d) => render_dungeon(&d, arguments.format),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(2);
+            }
+        }
+    };
+
+    match arguments.output {
+        Some(path) => {
+            if let Err(e) = fs::write(&path, text) {
+                eprintln!("error: can not write '{}': {}", path, e);
+                process::exit(1);
+            }
+        },
+        None => print!("{}", text)
+    }
+}
@@ -0,0 +1,306 @@
+use super::preudo_random::PseudoRandom;
+use super::room_generator::Room;
+use super::cave_generator::floor_regions;
+use crate::level::options::{Options, MazeAlgorithm, RoomType};
+
+// maze cells are on the odd lattice: cells are at border + 2 * i, tiles between them are walls or passages
+// rooms also start and end at lattice cells, so there is at least one wall between the room and the maze
+struct Lattice {
+    border: usize,
+    rows: usize,
+    columns: usize,
+    floor: Vec<Vec<bool>>,
+    in_room: Vec<Vec<bool>>
+}
+
+impl Lattice {
+    fn tile(&self, cell: (usize, usize)) -> (usize, usize) {
+        return (self.border + 2 * cell.0, self.border + 2 * cell.1);
+    }
+
+    fn neighbours(&self, cell: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut to_return: Vec<(usize, usize)> = Vec::with_capacity(4);
+        if cell.0 > 0 {
+            to_return.push((cell.0 - 1, cell.1));
+        }
+        if cell.1 + 1 < self.columns {
+            to_return.push((cell.0, cell.1 + 1));
+        }
+        if cell.0 + 1 < self.rows {
+            to_return.push((cell.0 + 1, cell.1));
+        }
+        if cell.1 > 0 {
+            to_return.push((cell.0, cell.1 - 1));
+        }
+        return to_return;
+    }
+
+    fn is_room(&self, cell: (usize, usize)) -> bool {
+        let (x, y) = self.tile(cell);
+        return self.in_room[x][y];
+    }
+
+    fn is_floor(&self, cell: (usize, usize)) -> bool {
+        let (x, y) = self.tile(cell);
+        return self.floor[x][y];
+    }
+
+    fn carve(&mut self, cell: (usize, usize)) {
+        let (x, y) = self.tile(cell);
+        self.floor[x][y] = true;
+    }
+
+    // the tile between two neighbouring cells
+    fn wall(&self, a: (usize, usize), b: (usize, usize)) -> (usize, usize) {
+        let (ax, ay) = self.tile(a);
+        let (bx, by) = self.tile(b);
+        return ((ax + bx) / 2, (ay + by) / 2);
+    }
+
+    fn is_open(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        let (x, y) = self.wall(a, b);
+        return self.floor[x][y];
+    }
+
+    fn open(&mut self, a: (usize, usize), b: (usize, usize)) {
+        self.carve(a);
+        self.carve(b);
+        let (x, y) = self.wall(a, b);
+        self.floor[x][y] = true;
+    }
+
+    // maze cells are all cells outside of rooms
+    fn maze_cells(&self) -> Vec<(usize, usize)> {
+        let mut to_return: Vec<(usize, usize)> = Vec::with_capacity(self.rows * self.columns);
+        for i in 0..self.rows {
+            for j in 0..self.columns {
+                if !self.is_room((i, j)) {
+                    to_return.push((i, j));
+                }
+            }
+        }
+        return to_return;
+    }
+}
+
+// floor of the maze with rooms, dead_ends_count is the number of dead ends after braiding
+pub struct Maze {
+    pub floor: Vec<Vec<bool>>,
+    pub rooms: Vec<Room>,
+    pub dead_ends_count: usize
+}
+
+// rooms and mazes: at first rooms are placed on the lattice, then the rest of the lattice is filled by the maze
+// all regions are connected through walls by the random spanning tree, finally dead ends are opened with the probability braid_factor
+pub fn generate_maze(random: &mut PseudoRandom, options: &Options) -> Maze {
+    let settings = options.maze_settings();
+    let height = options.level_height();
+    let width = options.level_width();
+    let border = options.border();
+    let inner_height = height.saturating_sub(2 * border);
+    let inner_width = width.saturating_sub(2 * border);
+    let mut lattice = Lattice {
+        border,
+        rows: inner_height.div_ceil(2),
+        columns: inner_width.div_ceil(2),
+        floor: vec![vec![false; width]; height],
+        in_room: vec![vec![false; width]; height]
+    };
+    if lattice.rows == 0 || lattice.columns == 0 {
+        return Maze { floor: lattice.floor, rooms: Vec::new(), dead_ends_count: 0 };
+    }
+
+    let rooms = place_rooms(random, options, &mut lattice);
+    match settings.algorithm() {
+        MazeAlgorithm::Backtracker => carve_backtracker(random, &mut lattice),
+        MazeAlgorithm::Prim => carve_prim(random, &mut lattice),
+        MazeAlgorithm::Kruskal => carve_kruskal(random, &mut lattice)
+    };
+    connect_regions(random, &mut lattice);
+    braid(random, &mut lattice, settings.braid_factor());
+    let dead_ends_count = lattice.maze_cells().into_iter().filter(|&c| open_sides(&lattice, c) == 1).count();
+    return Maze { floor: lattice.floor, rooms, dead_ends_count };
+}
+
+fn place_rooms(random: &mut PseudoRandom, options: &Options, lattice: &mut Lattice) -> Vec<Room> {
+    let mut rooms: Vec<Room> = Vec::new();
+    for _ in 0..options.maze_settings().room_attempts() {
+        let room_width = random.next_odd(options.min_room_width(), options.max_room_width());
+        let room_height = random.next_odd(options.min_room_height(), options.max_room_height());
+        let room_rows = room_height.div_ceil(2);
+        let room_columns = room_width.div_ceil(2);
+        if room_rows > lattice.rows || room_columns > lattice.columns {
+            continue;
+        }
+        let (room_x, room_y) = lattice.tile((random.next(0, lattice.rows - room_rows), random.next(0, lattice.columns - room_columns)));
+        let room = Room::new(room_height, room_width, room_x, room_y, RoomType::Rectangle);
+        if rooms.iter().any(|r| r.intersects(&room, 0)) {
+            continue;
+        }
+        for x in room_x..room_x + room_height {
+            for y in room_y..room_y + room_width {
+                lattice.floor[x][y] = true;
+                lattice.in_room[x][y] = true;
+            }
+        }
+        rooms.push(room);
+    }
+    return rooms;
+}
+
+fn unvisited_neighbours(lattice: &Lattice, cell: (usize, usize)) -> Vec<(usize, usize)> {
+    return lattice.neighbours(cell).into_iter().filter(|&n| !lattice.is_room(n) && !lattice.is_floor(n)).collect();
+}
+
+// rooms can split the lattice into several parts, so each algorithm starts again from each unvisited cell
+fn carve_backtracker(random: &mut PseudoRandom, lattice: &mut Lattice) {
+    for start in lattice.maze_cells() {
+        if lattice.is_floor(start) {
+            continue;
+        }
+        lattice.carve(start);
+        let mut stack: Vec<(usize, usize)> = vec![start];
+        while let Some(&cell) = stack.last() {
+            let candidates = unvisited_neighbours(lattice, cell);
+            if candidates.is_empty() {
+                stack.pop();
+                continue;
+            }
+            let next = candidates[random.next(0, candidates.len() - 1)];
+            lattice.open(cell, next);
+            stack.push(next);
+        }
+    }
+}
+
+fn carve_prim(random: &mut PseudoRandom, lattice: &mut Lattice) {
+    for start in lattice.maze_cells() {
+        if lattice.is_floor(start) {
+            continue;
+        }
+        lattice.carve(start);
+        let mut frontier: Vec<((usize, usize), (usize, usize))> = unvisited_neighbours(lattice, start).into_iter().map(|n| (start, n)).collect();
+        while !frontier.is_empty() {
+            let (from, to) = frontier.swap_remove(random.next(0, frontier.len() - 1));
+            if lattice.is_floor(to) {
+                continue;
+            }
+            lattice.open(from, to);
+            for n in unvisited_neighbours(lattice, to) {
+                frontier.push((to, n));
+            }
+        }
+    }
+}
+
+fn find_set(parents: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    let mut current = index;
+    while parents[current] != root {
+        let next = parents[current];
+        parents[current] = root;
+        current = next;
+    }
+    return root;
+}
+
+fn shuffle<T>(random: &mut PseudoRandom, values: &mut [T]) {
+    for i in (1..values.len()).rev() {
+        let j = random.next(0, i);
+        values.swap(i, j);
+    }
+}
+
+fn carve_kruskal(random: &mut PseudoRandom, lattice: &mut Lattice) {
+    let mut edges: Vec<((usize, usize), (usize, usize))> = Vec::new();
+    for cell in lattice.maze_cells() {
+        lattice.carve(cell);
+        for n in [(cell.0 + 1, cell.1), (cell.0, cell.1 + 1)] {
+            if n.0 < lattice.rows && n.1 < lattice.columns && !lattice.is_room(n) {
+                edges.push((cell, n));
+            }
+        }
+    }
+    shuffle(random, &mut edges);
+
+    let mut parents: Vec<usize> = (0..lattice.rows * lattice.columns).collect();
+    for (a, b) in edges {
+        let root_a = find_set(&mut parents, a.0 * lattice.columns + a.1);
+        let root_b = find_set(&mut parents, b.0 * lattice.columns + b.1);
+        if root_a != root_b {
+            parents[root_a] = root_b;
+            lattice.open(a, b);
+        }
+    }
+}
+
+// rooms and separate parts of the maze are connected by opening walls between them along the random spanning tree
+fn connect_regions(random: &mut PseudoRandom, lattice: &mut Lattice) {
+    let regions = floor_regions(&lattice.floor);
+    let mut region_index: Vec<Vec<usize>> = vec![vec![usize::MAX; lattice.floor[0].len()]; lattice.floor.len()];
+    for (index, region) in regions.iter().enumerate() {
+        for &(x, y) in region {
+            region_index[x][y] = index;
+        }
+    }
+
+    let mut connectors: Vec<((usize, usize), (usize, usize))> = Vec::new();
+    for i in 0..lattice.rows {
+        for j in 0..lattice.columns {
+            for n in [(i + 1, j), (i, j + 1)] {
+                if n.0 < lattice.rows && n.1 < lattice.columns && !lattice.is_open((i, j), n) {
+                    let (ax, ay) = lattice.tile((i, j));
+                    let (bx, by) = lattice.tile(n);
+                    if region_index[ax][ay] != region_index[bx][by] {
+                        connectors.push(((i, j), n));
+                    }
+                }
+            }
+        }
+    }
+    shuffle(random, &mut connectors);
+
+    let mut parents: Vec<usize> = (0..regions.len()).collect();
+    for (a, b) in connectors {
+        let (ax, ay) = lattice.tile(a);
+        let (bx, by) = lattice.tile(b);
+        let root_a = find_set(&mut parents, region_index[ax][ay]);
+        let root_b = find_set(&mut parents, region_index[bx][by]);
+        if root_a != root_b {
+            parents[root_a] = root_b;
+            lattice.open(a, b);
+        }
+    }
+}
+
+fn open_sides(lattice: &Lattice, cell: (usize, usize)) -> usize {
+    return lattice.neighbours(cell).into_iter().filter(|&n| lattice.is_open(cell, n)).count();
+}
+
+// each dead end is opened to the neighbour cell with the probability braid_factor
+fn braid(random: &mut PseudoRandom, lattice: &mut Lattice, braid_factor: f64) {
+    if braid_factor <= 0.0 {
+        return;
+    }
+
+    let mut dead_ends: Vec<(usize, usize)> = lattice.maze_cells().into_iter().filter(|&c| open_sides(lattice, c) == 1).collect();
+    shuffle(random, &mut dead_ends);
+    for cell in dead_ends {
+        // the dead end can be already opened from the other dead end
+        if open_sides(lattice, cell) != 1 || random.next_float() >= braid_factor {
+            continue;
+        }
+        let closed: Vec<(usize, usize)> = lattice.neighbours(cell).into_iter().filter(|&n| !lattice.is_open(cell, n)).collect();
+        // prefer other dead ends, so one passage removes two of them
+        let dead_neighbours: Vec<(usize, usize)> = closed.iter().copied().filter(|&n| !lattice.is_room(n) && open_sides(lattice, n) == 1).collect();
+        let candidates = if !dead_neighbours.is_empty() { dead_neighbours } else { closed };
+        if candidates.is_empty() {
+            continue;
+        }
+        let next = candidates[random.next(0, candidates.len() - 1)];
+        lattice.open(cell, next);
+    }
+}
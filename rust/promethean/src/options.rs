@@ -1,12 +1,448 @@
 use super::point::Point;
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+// the value of RoomType::id for all custom shapes
+pub const CUSTOM_ROOM_TYPE_ID: u8 = 255;
+// the value of RoomType::id for all prefab rooms
+pub const PREFAB_ROOM_TYPE_ID: u8 = 254;
+// the value of RoomType::id for rooms, which are turned into caverns
+pub const CAVERN_ROOM_TYPE_ID: u8 = 253;
 
-#[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum RoomType {
-    Square = 0,
-    Rectangle = 1,
-    Cross = 2,
-    Diamond = 3
+    Square,
+    Rectangle,
+    Cross,
+    Diamond,
+    Circle,
+    Ellipse,
+    // the circle with the solid core
+    Ring,
+    // composite rooms from several rectangles, they are randomly rotated or mirrored
+    LShape,
+    TShape,
+    UShape,
+    // the plus with arms of different length
+    OffsetPlus,
+    // the shape, registered in the LevelGenerator, the value is the index of the shape
+    Custom(usize),
+    // the room from the ascii template, registered in the LevelGenerator, the value is the index of the prefab
+    Prefab(usize),
+    // the room with the cave floor, grown inside the bounding box of the generated room
+    Cavern
+}
+
+impl RoomType {
+    // numeric value, used in WASM API
+    pub fn id(&self) -> u8 {
+        return match self {
+            RoomType::Square => 0,
+            RoomType::Rectangle => 1,
+            RoomType::Cross => 2,
+            RoomType::Diamond => 3,
+            RoomType::Circle => 4,
+            RoomType::Ellipse => 5,
+            RoomType::Ring => 6,
+            RoomType::LShape => 7,
+            RoomType::TShape => 8,
+            RoomType::UShape => 9,
+            RoomType::OffsetPlus => 10,
+            RoomType::Custom(_) => CUSTOM_ROOM_TYPE_ID,
+            RoomType::Prefab(_) => PREFAB_ROOM_TYPE_ID,
+            RoomType::Cavern => CAVERN_ROOM_TYPE_ID
+        };
+    }
+
+    // return built-in room type by numeric value
+    pub fn from_id(id: u8) -> Option<RoomType> {
+        return match id {
+            0 => Some(RoomType::Square),
+            1 => Some(RoomType::Rectangle),
+            2 => Some(RoomType::Cross),
+            3 => Some(RoomType::Diamond),
+            4 => Some(RoomType::Circle),
+            5 => Some(RoomType::Ellipse),
+            6 => Some(RoomType::Ring),
+            7 => Some(RoomType::LShape),
+            8 => Some(RoomType::TShape),
+            9 => Some(RoomType::UShape),
+            10 => Some(RoomType::OffsetPlus),
+            _ => None
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConnectionStrategy {
+    // connect each room with the next one, rooms are ordered by the distance from the level corner
+    Chain = 0,
+    // connect rooms along the minimum spanning tree of room centers
+    MinimumSpanningTree = 1,
+    // connect the closest rooms of sibling nodes of the BSP tree, works only with the BSP room placement
+    BspSiblings = 2
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RoomPlacement {
+    // rooms are placed at random positions, intersected rooms are moved to the closest free position
+    Random = 0,
+    // the level is recursively split into parts, each part contains one room
+    Bsp = 1,
+    // room centers are Poisson-disk samples, so rooms are evenly spread over the level
+    Poisson = 2
+}
+
+// what to do, when the path between two rooms is not found
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    // how many times the search limit is doubled for the failed corridor
+    limit_raises: usize,
+    // the maximum number of other room pairs, which are used to connect disconnected parts of the level
+    partner_attempts: usize,
+    // rebuild the pathing grid without room borders and try to connect disconnected parts again
+    relax_room_borders: bool
+}
+
+impl RetryPolicy {
+    pub fn new(limit_raises: usize, partner_attempts: usize, relax_room_borders: bool) -> RetryPolicy {
+        return RetryPolicy { limit_raises, partner_attempts, relax_room_borders };
+    }
+
+    pub fn new_default() -> RetryPolicy {
+        return RetryPolicy::new(2, 64, true);
+    }
+
+    // skip failed corridors, as the previous versions do
+    pub fn new_disabled() -> RetryPolicy {
+        return RetryPolicy::new(0, 0, false);
+    }
+
+    pub fn limit_raises(&self) -> usize {
+        return self.limit_raises;
+    }
+
+    pub fn partner_attempts(&self) -> usize {
+        return self.partner_attempts;
+    }
+
+    pub fn relax_room_borders(&self) -> bool {
+        return self.relax_room_borders;
+    }
+}
+
+// what kind of level is generated
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GenerationMode {
+    // rooms connected by corridors
+    Rooms = 0,
+    // caves of the cellular automaton
+    Caves = 1,
+    // tunnels of random walkers with rooms at turning points
+    Tunnels = 2,
+    // the maze on the odd lattice with embedded rooms
+    Maze = 3,
+    // wave function collapse with patterns of the sample level
+    Wfc = 4
+}
+
+// random walkers for tunnels, all walkers start at the level center
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TunnelSettings {
+    // the number of walkers, which move at the same time
+    walkers: usize,
+    // the probability to change the direction at each step
+    turn_probability: f64,
+    // the number of steps of each walker, after it the new walker starts from the random floor tile
+    lifetime: usize,
+    // the fraction of the level inside the border, which should become the floor
+    coverage: f64,
+    // the probability to place the room at the turning point, room sizes are from the common size range
+    room_probability: f64
+}
+
+impl TunnelSettings {
+    pub fn new(walkers: usize, turn_probability: f64, lifetime: usize, coverage: f64, room_probability: f64) -> TunnelSettings {
+        return TunnelSettings { walkers, turn_probability, lifetime, coverage, room_probability };
+    }
+
+    pub fn new_default() -> TunnelSettings {
+        return TunnelSettings::new(4, 0.15, 100, 0.3, 0.1);
+    }
+
+    pub fn walkers(&self) -> usize {
+        return self.walkers;
+    }
+
+    pub fn turn_probability(&self) -> f64 {
+        return self.turn_probability;
+    }
+
+    pub fn lifetime(&self) -> usize {
+        return self.lifetime;
+    }
+
+    pub fn coverage(&self) -> f64 {
+        return self.coverage;
+    }
+
+    pub fn room_probability(&self) -> f64 {
+        return self.room_probability;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MazeAlgorithm {
+    // the depth-first search with the random order of neighbours, long winding passages
+    Backtracker = 0,
+    // randomized Prim's algorithm, many short dead ends
+    Prim = 1,
+    // randomized Kruskal's algorithm, passages without the visible direction
+    Kruskal = 2
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MazeSettings {
+    algorithm: MazeAlgorithm,
+    // the probability to remove each dead end, 0 gives the perfect maze
+    braid_factor: f64,
+    // how many times to try to place the room before the maze, room sizes are from the common size range
+    room_attempts: usize
+}
+
+impl MazeSettings {
+    pub fn new(algorithm: MazeAlgorithm, braid_factor: f64, room_attempts: usize) -> MazeSettings {
+        return MazeSettings { algorithm, braid_factor, room_attempts };
+    }
+
+    pub fn new_default() -> MazeSettings {
+        return MazeSettings::new(MazeAlgorithm::Backtracker, 0.0, 0);
+    }
+
+    pub fn algorithm(&self) -> MazeAlgorithm {
+        return self.algorithm;
+    }
+
+    pub fn braid_factor(&self) -> f64 {
+        return self.braid_factor;
+    }
+
+    pub fn room_attempts(&self) -> usize {
+        return self.room_attempts;
+    }
+}
+
+// the overlapping model of the wave function collapse
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WfcSettings {
+    // patterns are pattern_size x pattern_size squares of the sample
+    pattern_size: usize,
+    // add rotated and mirrored patterns
+    symmetry: bool,
+    // how many times the generation starts again after the contradiction
+    max_restarts: usize
+}
+
+impl WfcSettings {
+    pub fn new(pattern_size: usize, symmetry: bool, max_restarts: usize) -> WfcSettings {
+        return WfcSettings { pattern_size, symmetry, max_restarts };
+    }
+
+    pub fn new_default() -> WfcSettings {
+        return WfcSettings::new(3, true, 10);
+    }
+
+    pub fn pattern_size(&self) -> usize {
+        return self.pattern_size;
+    }
+
+    pub fn symmetry(&self) -> bool {
+        return self.symmetry;
+    }
+
+    pub fn max_restarts(&self) -> usize {
+        return self.max_restarts;
+    }
+}
+
+// how the floor of cavern rooms is grown
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CavernMethod {
+    // the cellular automaton of caves inside the room
+    CellularAutomata = 0,
+    // random walk from the room center
+    DrunkardWalk = 1
+}
+
+// the cellular automaton for caves, cells of the automaton are walls
+// bit i of birth and survival is set, if the cell becomes or stays the wall with i wall neighbours
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CaveSettings {
+    // the fraction of wall cells in the initial random level
+    fill_ratio: f64,
+    birth: u16,
+    survival: u16,
+    iterations: usize,
+    // floor regions with less cells are filled by walls
+    min_region_size: usize
+}
+
+impl CaveSettings {
+    pub fn new(fill_ratio: f64, birth: u16, survival: u16, iterations: usize, min_region_size: usize) -> CaveSettings {
+        return CaveSettings { fill_ratio, birth, survival, iterations, min_region_size };
+    }
+
+    // the classic 4-5 rule, the wall appears with 5 and more wall neighbours and stays with 4 and more
+    pub fn new_default() -> CaveSettings {
+        return CaveSettings::new(0.45, 0b111100000, 0b111110000, 5, 16);
+    }
+
+    // the rule in the form B5678/S45678, the order of parts is not important
+    pub fn parse_rule(rule: &str) -> Result<(u16, u16), OptionsError> {
+        let error = || OptionsError::InvalidCaveRule { rule: rule.to_string() };
+        let mut birth: Option<u16> = None;
+        let mut survival: Option<u16> = None;
+        for part in rule.trim().split('/') {
+            let mut chars = part.trim().chars();
+            let target = match chars.next() {
+                Some('B') | Some('b') => &mut birth,
+                Some('S') | Some('s') => &mut survival,
+                _ => return Err(error())
+            };
+            if target.is_some() {
+                return Err(error());
+            }
+            let mut mask: u16 = 0;
+            for c in chars {
+                match c.to_digit(10) {
+                    Some(d) if d <= 8 => mask |= 1 << d,
+                    _ => return Err(error())
+                }
+            }
+            *target = Some(mask);
+        }
+        return match (birth, survival) {
+            (Some(b), Some(s)) => Ok((b, s)),
+            _ => Err(error())
+        };
+    }
+
+    pub fn fill_ratio(&self) -> f64 {
+        return self.fill_ratio;
+    }
+
+    pub fn birth(&self) -> u16 {
+        return self.birth;
+    }
+
+    pub fn survival(&self) -> u16 {
+        return self.survival;
+    }
+
+    pub fn iterations(&self) -> usize {
+        return self.iterations;
+    }
+
+    pub fn min_region_size(&self) -> usize {
+        return self.min_region_size;
+    }
+
+    // is the cell a wall at the next step
+    pub fn is_wall(&self, is_wall: bool, wall_neighbours: usize) -> bool {
+        let mask = if is_wall { self.survival } else { self.birth };
+        return mask & (1 << wall_neighbours) != 0;
+    }
+}
+
+// the range of room sizes, width and height are both included
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RoomSizeRange {
+    min_width: usize,
+    max_width: usize,
+    min_height: usize,
+    max_height: usize
+}
+
+impl RoomSizeRange {
+    pub fn new(min_width: usize, max_width: usize, min_height: usize, max_height: usize) -> RoomSizeRange {
+        return RoomSizeRange { min_width, max_width, min_height, max_height };
+    }
+
+    pub fn min_width(&self) -> usize {
+        return self.min_width;
+    }
+
+    pub fn max_width(&self) -> usize {
+        return self.max_width;
+    }
+
+    pub fn min_height(&self) -> usize {
+        return self.min_height;
+    }
+
+    pub fn max_height(&self) -> usize {
+        return self.max_height;
+    }
+}
+
+// the weight of the room type and its own size range, other room types use default weight 1 and the common size range
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RoomTypeSettings {
+    room_type: RoomType,
+    weight: usize,
+    size: Option<RoomSizeRange>
+}
+
+impl RoomTypeSettings {
+    pub fn new(room_type: RoomType, weight: usize, size: Option<RoomSizeRange>) -> RoomTypeSettings {
+        return RoomTypeSettings { room_type, weight, size };
+    }
+
+    pub fn room_type(&self) -> RoomType {
+        return self.room_type;
+    }
+
+    pub fn weight(&self) -> usize {
+        return self.weight;
+    }
+
+    pub fn size(&self) -> Option<RoomSizeRange> {
+        return self.size;
+    }
+}
+
+// how many copies of the prefab room are placed in the level
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PrefabPlacement {
+    // the index of the prefab in the generator
+    prefab: usize,
+    min_count: usize,
+    max_count: usize,
+    // if true, then the prefab can be rotated and mirrored
+    allow_rotation: bool
+}
+
+impl PrefabPlacement {
+    pub fn new(prefab: usize, min_count: usize, max_count: usize, allow_rotation: bool) -> PrefabPlacement {
+        return PrefabPlacement { prefab, min_count, max_count, allow_rotation };
+    }
+
+    pub fn prefab(&self) -> usize {
+        return self.prefab;
+    }
+
+    pub fn min_count(&self) -> usize {
+        return self.min_count;
+    }
+
+    pub fn max_count(&self) -> usize {
+        return self.max_count;
+    }
+
+    pub fn allow_rotation(&self) -> bool {
+        return self.allow_rotation;
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -31,7 +467,17 @@ pub enum Tile {
     TopLeftOutsideCorner = 10,
     TopRightOutsideCorner = 11,
     BottomLeftOutsideCorner = 12,
-    BottomRightOutsideCorner = 13
+    BottomRightOutsideCorner = 13,
+    // stairs of multi-floor dungeons, placed on the floor
+    StairsDown = 14,
+    StairsUp = 15
+}
+
+impl Tile {
+    // floor and stairs
+    pub fn is_walkable(&self) -> bool {
+        return matches!(self, Tile::Floor | Tile::StairsDown | Tile::StairsUp);
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -74,7 +520,26 @@ pub struct Options {
     border: usize,
     room_border: usize,
     overlap_rooms: bool,
-    room_types: Vec<RoomType>
+    room_types: Vec<RoomType>,
+    generation_mode: GenerationMode,
+    cave_settings: CaveSettings,
+    tunnel_settings: TunnelSettings,
+    maze_settings: MazeSettings,
+    wfc_settings: WfcSettings,
+    // the fraction of generated rooms, which are turned into caverns
+    cavern_ratio: f64,
+    cavern_method: CavernMethod,
+    room_type_settings: Vec<RoomTypeSettings>,
+    room_placement: RoomPlacement,
+    // the minimal distance between room centers for the Poisson placement, 0 means automatic distance
+    min_room_distance: usize,
+    // how many times random placement retries rooms, which can not be placed, 0 means that the placement stops at the first failure
+    placement_attempts: usize,
+    connection_strategy: ConnectionStrategy,
+    loop_ratio: f64,
+    search_limit: usize,
+    retry_policy: RetryPolicy,
+    prefabs: Vec<PrefabPlacement>
 }
 
 impl Options {
@@ -91,10 +556,27 @@ impl Options {
             border: 1,
             room_border: 1,
             overlap_rooms: false,
-            room_types: vec![RoomType::Square, RoomType::Rectangle, RoomType::Cross, RoomType::Diamond]
+            room_types: vec![RoomType::Square, RoomType::Rectangle, RoomType::Cross, RoomType::Diamond],
+            generation_mode: GenerationMode::Rooms,
+            cave_settings: CaveSettings::new_default(),
+            tunnel_settings: TunnelSettings::new_default(),
+            maze_settings: MazeSettings::new_default(),
+            wfc_settings: WfcSettings::new_default(),
+            cavern_ratio: 0.0,
+            cavern_method: CavernMethod::CellularAutomata,
+            room_type_settings: Vec::new(),
+            room_placement: RoomPlacement::Random,
+            min_room_distance: 0,
+            placement_attempts: 100,
+            connection_strategy: ConnectionStrategy::MinimumSpanningTree,
+            loop_ratio: 0.0,
+            search_limit: 2000,
+            retry_policy: RetryPolicy::new_default(),
+            prefabs: Vec::new()
         };
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn new(level_width: usize,
                level_height: usize,
                min_room_width: usize,
@@ -119,10 +601,27 @@ impl Options {
             border,
             room_border,
             overlap_rooms,
-            room_types
+            room_types,
+            generation_mode: GenerationMode::Rooms,
+            cave_settings: CaveSettings::new_default(),
+            tunnel_settings: TunnelSettings::new_default(),
+            maze_settings: MazeSettings::new_default(),
+            wfc_settings: WfcSettings::new_default(),
+            cavern_ratio: 0.0,
+            cavern_method: CavernMethod::CellularAutomata,
+            room_type_settings: Vec::new(),
+            room_placement: RoomPlacement::Random,
+            min_room_distance: 0,
+            placement_attempts: 100,
+            connection_strategy: ConnectionStrategy::MinimumSpanningTree,
+            loop_ratio: 0.0,
+            search_limit: 2000,
+            retry_policy: RetryPolicy::new_default(),
+            prefabs: Vec::new()
         };
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn set(&mut self, level_width: usize,
                       level_height: usize,
                       min_room_width: usize,
@@ -198,6 +697,92 @@ impl Options {
         self.room_types = room_types;
     }
 
+    fn room_type_settings_mut(&mut self, room_type: RoomType) -> &mut RoomTypeSettings {
+        let index = match self.room_type_settings.iter().position(|s| s.room_type == room_type) {
+            Some(i) => i,
+            None => {
+                self.room_type_settings.push(RoomTypeSettings::new(room_type, 1, None));
+                self.room_type_settings.len() - 1
+            }
+        };
+        return &mut self.room_type_settings[index];
+    }
+
+    // the room type is selected with the probability weight / (sum of weights of all allowed types)
+    pub fn set_room_type_weight(&mut self, room_type: RoomType, weight: usize) {
+        self.room_type_settings_mut(room_type).weight = weight;
+    }
+
+    // None means the common size range
+    pub fn set_room_type_size(&mut self, room_type: RoomType, size: Option<RoomSizeRange>) {
+        self.room_type_settings_mut(room_type).size = size;
+    }
+
+    pub fn reset_room_type_settings(&mut self) {
+        self.room_type_settings.clear();
+    }
+
+    pub fn set_generation_mode(&mut self, generation_mode: GenerationMode) {
+        self.generation_mode = generation_mode;
+    }
+
+    pub fn set_cave_settings(&mut self, cave_settings: CaveSettings) {
+        self.cave_settings = cave_settings;
+    }
+
+    pub fn set_tunnel_settings(&mut self, tunnel_settings: TunnelSettings) {
+        self.tunnel_settings = tunnel_settings;
+    }
+
+    pub fn set_maze_settings(&mut self, maze_settings: MazeSettings) {
+        self.maze_settings = maze_settings;
+    }
+
+    pub fn set_wfc_settings(&mut self, wfc_settings: WfcSettings) {
+        self.wfc_settings = wfc_settings;
+    }
+
+    pub fn set_caverns(&mut self, cavern_ratio: f64, cavern_method: CavernMethod) {
+        self.cavern_ratio = cavern_ratio;
+        self.cavern_method = cavern_method;
+    }
+
+    pub fn set_room_placement(&mut self, room_placement: RoomPlacement) {
+        self.room_placement = room_placement;
+    }
+
+    pub fn set_min_room_distance(&mut self, min_room_distance: usize) {
+        self.min_room_distance = min_room_distance;
+    }
+
+    pub fn set_placement_attempts(&mut self, placement_attempts: usize) {
+        self.placement_attempts = placement_attempts;
+    }
+
+    pub fn set_connection_strategy(&mut self, connection_strategy: ConnectionStrategy) {
+        self.connection_strategy = connection_strategy;
+    }
+
+    pub fn set_loop_ratio(&mut self, loop_ratio: f64) {
+        self.loop_ratio = loop_ratio;
+    }
+
+    pub fn set_search_limit(&mut self, search_limit: usize) {
+        self.search_limit = search_limit;
+    }
+
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    pub fn set_prefabs(&mut self, prefabs: Vec<PrefabPlacement>) {
+        self.prefabs = prefabs;
+    }
+
+    pub fn add_prefab(&mut self, placement: PrefabPlacement) {
+        self.prefabs.push(placement);
+    }
+
     pub fn add_room_type(&mut self, room_type: RoomType) {
         if !self.room_types.contains(&room_type) {
             self.room_types.push(room_type);
@@ -205,12 +790,10 @@ impl Options {
     }
 
     pub fn remove_room_type(&mut self, room_type: RoomType) {
+        self.room_type_settings.retain(|s| s.room_type != room_type);
         let res_index = self.room_types.iter().position(|&r| r == room_type);
-        match res_index {
-            Some(index) => {
-                self.room_types.remove(index);
-            },
-            None => {},
+        if let Some(index) = res_index {
+            self.room_types.remove(index);
         }
     }
 
@@ -218,6 +801,65 @@ impl Options {
         return &self.room_types;
     }
 
+    pub fn generation_mode(&self) -> GenerationMode {
+        return self.generation_mode;
+    }
+
+    pub fn cave_settings(&self) -> &CaveSettings {
+        return &self.cave_settings;
+    }
+
+    pub fn tunnel_settings(&self) -> &TunnelSettings {
+        return &self.tunnel_settings;
+    }
+
+    pub fn maze_settings(&self) -> &MazeSettings {
+        return &self.maze_settings;
+    }
+
+    pub fn wfc_settings(&self) -> &WfcSettings {
+        return &self.wfc_settings;
+    }
+
+    pub fn cavern_ratio(&self) -> f64 {
+        return self.cavern_ratio;
+    }
+
+    pub fn cavern_method(&self) -> CavernMethod {
+        return self.cavern_method;
+    }
+
+    pub fn room_placement(&self) -> RoomPlacement {
+        return self.room_placement;
+    }
+
+    pub fn min_room_distance(&self) -> usize {
+        return self.min_room_distance;
+    }
+
+    pub fn placement_attempts(&self) -> usize {
+        return self.placement_attempts;
+    }
+
+    pub fn room_type_settings(&self) -> &Vec<RoomTypeSettings> {
+        return &self.room_type_settings;
+    }
+
+    pub fn room_type_weight(&self, room_type: RoomType) -> usize {
+        return match self.room_type_settings.iter().find(|s| s.room_type == room_type) {
+            Some(settings) => settings.weight,
+            None => 1
+        };
+    }
+
+    // the size range of the room type, or the common range, if the type has no own range
+    pub fn room_type_size(&self, room_type: RoomType) -> RoomSizeRange {
+        return match self.room_type_settings.iter().find(|s| s.room_type == room_type).and_then(|s| s.size) {
+            Some(size) => size,
+            None => RoomSizeRange::new(self.min_room_width, self.max_room_width, self.min_room_height, self.max_room_height)
+        };
+    }
+
     pub fn min_room_width(&self) -> usize {
         return self.min_room_width;
     }
@@ -261,4 +903,504 @@ impl Options {
     pub fn random_seed(&self) -> usize {
         return self.random_seed;
     }
-}
\ No newline at end of file
+
+    pub fn connection_strategy(&self) -> ConnectionStrategy {
+        return self.connection_strategy;
+    }
+
+    pub fn loop_ratio(&self) -> f64 {
+        return self.loop_ratio;
+    }
+
+    pub fn search_limit(&self) -> usize {
+        return self.search_limit;
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        return &self.retry_policy;
+    }
+
+    pub fn prefabs(&self) -> &Vec<PrefabPlacement> {
+        return &self.prefabs;
+    }
+
+    // the common room size range
+    fn validate_room_size(&self) -> Result<(), OptionsError> {
+        if self.min_room_width == 0 || self.min_room_height == 0 {
+            return Err(OptionsError::ZeroRoomSize);
+        }
+
+        if self.min_room_width > self.max_room_width {
+            return Err(OptionsError::InvalidRoomWidthRange { min_room_width: self.min_room_width, max_room_width: self.max_room_width });
+        }
+
+        if self.min_room_height > self.max_room_height {
+            return Err(OptionsError::InvalidRoomHeightRange { min_room_height: self.min_room_height, max_room_height: self.max_room_height });
+        }
+        return Ok(());
+    }
+
+    pub fn validate(&self) -> Result<(), OptionsError> {
+        if self.level_width == 0 || self.level_height == 0 {
+            return Err(OptionsError::EmptyLevel { level_width: self.level_width, level_height: self.level_height });
+        }
+
+        if self.border == 0 {
+            return Err(OptionsError::ZeroBorder);
+        }
+
+        if self.room_border > self.border {
+            return Err(OptionsError::RoomBorderTooLarge { room_border: self.room_border, border: self.border });
+        }
+
+        // room options are not used by caves
+        if self.generation_mode == GenerationMode::Caves {
+            if !(0.0..=1.0).contains(&self.cave_settings.fill_ratio) {
+                return Err(OptionsError::InvalidFillRatio { fill_ratio: self.cave_settings.fill_ratio });
+            }
+            return Ok(());
+        }
+
+        if self.generation_mode == GenerationMode::Tunnels {
+            let tunnels = &self.tunnel_settings;
+            if tunnels.walkers == 0 || tunnels.lifetime == 0 {
+                return Err(OptionsError::NoTunnelWalkers { walkers: tunnels.walkers, lifetime: tunnels.lifetime });
+            }
+            if !(0.0..=1.0).contains(&tunnels.turn_probability) {
+                return Err(OptionsError::InvalidTurnProbability { turn_probability: tunnels.turn_probability });
+            }
+            if !(0.0..=1.0).contains(&tunnels.coverage) {
+                return Err(OptionsError::InvalidCoverage { coverage: tunnels.coverage });
+            }
+            if !(0.0..=1.0).contains(&tunnels.room_probability) {
+                return Err(OptionsError::InvalidRoomProbability { room_probability: tunnels.room_probability });
+            }
+            return self.validate_room_size();
+        }
+
+        if self.generation_mode == GenerationMode::Wfc {
+            if self.wfc_settings.pattern_size < 2 {
+                return Err(OptionsError::InvalidPatternSize { pattern_size: self.wfc_settings.pattern_size });
+            }
+            return Ok(());
+        }
+
+        if self.generation_mode == GenerationMode::Maze {
+            if !(0.0..=1.0).contains(&self.maze_settings.braid_factor) {
+                return Err(OptionsError::InvalidBraidFactor { braid_factor: self.maze_settings.braid_factor });
+            }
+            return self.validate_room_size();
+        }
+
+        if self.room_types.is_empty() {
+            return Err(OptionsError::EmptyRoomTypes);
+        }
+
+        for room_type in &self.room_types {
+            match room_type {
+                RoomType::Prefab(index) => return Err(OptionsError::PrefabRoomType { index: *index }),
+                RoomType::Cavern => return Err(OptionsError::CavernRoomType),
+                _ => {}
+            }
+        }
+
+        if self.room_types.iter().map(|&t| self.room_type_weight(t)).sum::<usize>() == 0 {
+            return Err(OptionsError::ZeroRoomTypeWeights);
+        }
+
+        if self.search_limit == 0 {
+            return Err(OptionsError::ZeroSearchLimit);
+        }
+
+        if self.connection_strategy == ConnectionStrategy::BspSiblings && self.room_placement != RoomPlacement::Bsp {
+            return Err(OptionsError::BspConnectionWithoutBsp);
+        }
+
+        if !(0.0..=1.0).contains(&self.loop_ratio) {
+            return Err(OptionsError::InvalidLoopRatio { loop_ratio: self.loop_ratio });
+        }
+
+        if !(0.0..=1.0).contains(&self.cavern_ratio) {
+            return Err(OptionsError::InvalidCavernRatio { cavern_ratio: self.cavern_ratio });
+        }
+
+        for placement in &self.prefabs {
+            if placement.min_count > placement.max_count {
+                return Err(OptionsError::InvalidPrefabCount { prefab: placement.prefab, min_count: placement.min_count, max_count: placement.max_count });
+            }
+        }
+
+        self.validate_room_size()?;
+
+        for settings in &self.room_type_settings {
+            if let Some(size) = settings.size {
+                if size.min_width == 0 || size.min_height == 0 {
+                    return Err(OptionsError::ZeroRoomSize);
+                }
+                if size.min_width > size.max_width {
+                    return Err(OptionsError::InvalidRoomWidthRange { min_room_width: size.min_width, max_room_width: size.max_width });
+                }
+                if size.min_height > size.max_height {
+                    return Err(OptionsError::InvalidRoomHeightRange { min_room_height: size.min_height, max_room_height: size.max_height });
+                }
+            }
+        }
+
+        // only rectangular, elliptical and composite rooms use the height range, other built-in rooms are square
+        // custom shapes can be of both kinds
+        let mut max_room_width: usize = 0;
+        let mut max_room_height: usize = 0;
+        for &room_type in &self.room_types {
+            let size = self.room_type_size(room_type);
+            let room_height = match room_type {
+                RoomType::Rectangle | RoomType::Ellipse | RoomType::LShape | RoomType::TShape | RoomType::UShape | RoomType::OffsetPlus => size.max_height,
+                RoomType::Custom(_) | RoomType::Prefab(_) => size.max_height.max(size.max_width),
+                _ => size.max_width
+            };
+            max_room_width = max_room_width.max(size.max_width);
+            max_room_height = max_room_height.max(room_height);
+        }
+
+        // rooms should fit into the level between two borders
+        if max_room_width + 2 * self.border > self.level_width {
+            return Err(OptionsError::RoomWiderThanLevel { max_room_width, level_width: self.level_width, border: self.border });
+        }
+
+        if max_room_height + 2 * self.border > self.level_height {
+            return Err(OptionsError::RoomHigherThanLevel { max_room_height, level_height: self.level_height, border: self.border });
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionsError {
+    EmptyLevel { level_width: usize, level_height: usize },
+    ZeroBorder,
+    RoomBorderTooLarge { room_border: usize, border: usize },
+    EmptyRoomTypes,
+    ZeroRoomSize,
+    InvalidRoomWidthRange { min_room_width: usize, max_room_width: usize },
+    InvalidRoomHeightRange { min_room_height: usize, max_room_height: usize },
+    RoomWiderThanLevel { max_room_width: usize, level_width: usize, border: usize },
+    RoomHigherThanLevel { max_room_height: usize, level_height: usize, border: usize },
+    InvalidLoopRatio { loop_ratio: f64 },
+    ZeroSearchLimit,
+    UnknownRoomShape { index: usize },
+    ZeroRoomTypeWeights,
+    BspConnectionWithoutBsp,
+    InvalidPrefabCount { prefab: usize, min_count: usize, max_count: usize },
+    UnknownPrefab { index: usize },
+    // prefabs are placed by PrefabPlacement, they can not be used as usual room types
+    PrefabRoomType { index: usize },
+    // there is no space in the level for the minimal number of prefab rooms
+    // it is not checked by the options builder, so generate() returns it for valid options, when prefabs do not fit
+    PrefabNotPlaced { prefab: usize, placed: usize, min_count: usize },
+    InvalidFillRatio { fill_ratio: f64 },
+    InvalidCaveRule { rule: String },
+    InvalidCavernRatio { cavern_ratio: f64 },
+    // caverns are made from generated rooms, they can not be used as usual room types
+    CavernRoomType,
+    NoTunnelWalkers { walkers: usize, lifetime: usize },
+    InvalidTurnProbability { turn_probability: f64 },
+    InvalidCoverage { coverage: f64 },
+    InvalidRoomProbability { room_probability: f64 },
+    InvalidBraidFactor { braid_factor: f64 },
+    InvalidPatternSize { pattern_size: usize },
+    // the wave function collapse mode requires the sample in the generator
+    NoWfcSample,
+    EmptyWfcSample,
+    WfcSampleTooSmall { pattern_size: usize, sample_height: usize, sample_width: usize },
+    WfcLevelTooSmall { pattern_size: usize, level_height: usize, level_width: usize, border: usize },
+    // each attempt of the wave function collapse ends by the contradiction
+    // it depends on the random choices, so generate() returns it for valid options, the other seed can succeed
+    WfcContradiction { restarts: usize },
+    NoFloors,
+    // there are no common room tiles on two neighbouring floors for stairs
+    StairsNotPlaced { floor: usize, attempts: usize }
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            OptionsError::EmptyLevel { level_width, level_height } => write!(f, "level size {}x{} is empty", level_width, level_height),
+            OptionsError::ZeroBorder => write!(f, "level border should be at least 1"),
+            OptionsError::RoomBorderTooLarge { room_border, border } => write!(f, "room border {} is larger than level border {}", room_border, border),
+            OptionsError::EmptyRoomTypes => write!(f, "at least one room type should be allowed"),
+            OptionsError::ZeroRoomSize => write!(f, "minimal room width and height should be at least 1"),
+            OptionsError::InvalidRoomWidthRange { min_room_width, max_room_width } => write!(f, "minimal room width {} is larger than maximal room width {}", min_room_width, max_room_width),
+            OptionsError::InvalidRoomHeightRange { min_room_height, max_room_height } => write!(f, "minimal room height {} is larger than maximal room height {}", min_room_height, max_room_height),
+            OptionsError::RoomWiderThanLevel { max_room_width, level_width, border } => write!(f, "room width {} does not fit into level width {} with border {}", max_room_width, level_width, border),
+            OptionsError::RoomHigherThanLevel { max_room_height, level_height, border } => write!(f, "room height {} does not fit into level height {} with border {}", max_room_height, level_height, border),
+            OptionsError::InvalidLoopRatio { loop_ratio } => write!(f, "loop ratio {} should be in [0, 1]", loop_ratio),
+            OptionsError::ZeroSearchLimit => write!(f, "path search limit should be at least 1"),
+            OptionsError::UnknownRoomShape { index } => write!(f, "custom room shape {} is not registered in the generator", index),
+            OptionsError::ZeroRoomTypeWeights => write!(f, "at least one allowed room type should have non-zero weight"),
+            OptionsError::BspConnectionWithoutBsp => write!(f, "BSP siblings connection requires BSP room placement"),
+            OptionsError::InvalidPrefabCount { prefab, min_count, max_count } => write!(f, "minimal count {} of prefab {} is larger than maximal count {}", min_count, prefab, max_count),
+            OptionsError::UnknownPrefab { index } => write!(f, "prefab {} is not registered in the generator", index),
+            OptionsError::PrefabRoomType { index } => write!(f, "prefab {} can not be used as a room type, add it as a prefab placement", index),
+            OptionsError::PrefabNotPlaced { prefab, placed, min_count } => write!(f, "only {} of at least {} copies of prefab {} fit into the level", placed, min_count, prefab),
+            OptionsError::InvalidFillRatio { fill_ratio } => write!(f, "cave fill ratio {} should be in [0, 1]", fill_ratio),
+            OptionsError::InvalidCaveRule { rule } => write!(f, "cave rule '{}' should have the form B5678/S45678", rule),
+            OptionsError::InvalidCavernRatio { cavern_ratio } => write!(f, "cavern ratio {} should be in [0, 1]", cavern_ratio),
+            OptionsError::CavernRoomType => write!(f, "cavern can not be used as a room type, set the cavern ratio instead"),
+            OptionsError::NoTunnelWalkers { walkers, lifetime } => write!(f, "tunnels need at least one walker with non-zero lifetime, but there are {} walkers with lifetime {}", walkers, lifetime),
+            OptionsError::InvalidTurnProbability { turn_probability } => write!(f, "turn probability {} should be in [0, 1]", turn_probability),
+            OptionsError::InvalidCoverage { coverage } => write!(f, "tunnel coverage {} should be in [0, 1]", coverage),
+            OptionsError::InvalidRoomProbability { room_probability } => write!(f, "room probability {} should be in [0, 1]", room_probability),
+            OptionsError::InvalidBraidFactor { braid_factor } => write!(f, "maze braid factor {} should be in [0, 1]", braid_factor),
+            OptionsError::InvalidPatternSize { pattern_size } => write!(f, "pattern size {} should be at least 2", pattern_size),
+            OptionsError::NoWfcSample => write!(f, "wave function collapse requires the sample, set it in the generator"),
+            OptionsError::EmptyWfcSample => write!(f, "wave function collapse sample is empty"),
+            OptionsError::WfcSampleTooSmall { pattern_size, sample_height, sample_width } => write!(f, "sample {}x{} is smaller than pattern size {}", sample_width, sample_height, pattern_size),
+            OptionsError::WfcLevelTooSmall { pattern_size, level_height, level_width, border } => write!(f, "level {}x{} with border {} is smaller than pattern size {}", level_width, level_height, border, pattern_size),
+            OptionsError::WfcContradiction { restarts } => write!(f, "wave function collapse ends by the contradiction after {} restarts", restarts),
+            OptionsError::NoFloors => write!(f, "dungeon should have at least one floor"),
+            OptionsError::StairsNotPlaced { floor, attempts } => write!(f, "stairs to floor {} can not be placed after {} attempts", floor, attempts)
+        };
+    }
+}
+
+impl std::error::Error for OptionsError {}
+
+impl From<OptionsError> for JsValue {
+    fn from(error: OptionsError) -> JsValue {
+        return JsError::new(&error.to_string()).into();
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OptionsBuilder {
+    options: Options
+}
+
+impl Default for OptionsBuilder {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl OptionsBuilder {
+    pub fn new() -> OptionsBuilder {
+        return OptionsBuilder { options: Options::new_default() };
+    }
+
+    pub fn level_width(mut self, level_width: usize) -> OptionsBuilder {
+        self.options.level_width = level_width;
+        return self;
+    }
+
+    pub fn level_height(mut self, level_height: usize) -> OptionsBuilder {
+        self.options.level_height = level_height;
+        return self;
+    }
+
+    pub fn min_room_width(mut self, min_room_width: usize) -> OptionsBuilder {
+        self.options.min_room_width = min_room_width;
+        return self;
+    }
+
+    pub fn max_room_width(mut self, max_room_width: usize) -> OptionsBuilder {
+        self.options.max_room_width = max_room_width;
+        return self;
+    }
+
+    pub fn min_room_height(mut self, min_room_height: usize) -> OptionsBuilder {
+        self.options.min_room_height = min_room_height;
+        return self;
+    }
+
+    pub fn max_room_height(mut self, max_room_height: usize) -> OptionsBuilder {
+        self.options.max_room_height = max_room_height;
+        return self;
+    }
+
+    pub fn number_of_rooms(mut self, number_of_rooms: usize) -> OptionsBuilder {
+        self.options.number_of_rooms = number_of_rooms;
+        return self;
+    }
+
+    pub fn random_seed(mut self, random_seed: usize) -> OptionsBuilder {
+        self.options.random_seed = random_seed;
+        return self;
+    }
+
+    pub fn border(mut self, border: usize) -> OptionsBuilder {
+        self.options.border = border;
+        return self;
+    }
+
+    pub fn room_border(mut self, room_border: usize) -> OptionsBuilder {
+        self.options.room_border = room_border;
+        return self;
+    }
+
+    pub fn overlap_rooms(mut self, overlap_rooms: bool) -> OptionsBuilder {
+        self.options.overlap_rooms = overlap_rooms;
+        return self;
+    }
+
+    pub fn room_types(mut self, room_types: Vec<RoomType>) -> OptionsBuilder {
+        self.options.room_types = room_types;
+        return self;
+    }
+
+    pub fn room_type_weight(mut self, room_type: RoomType, weight: usize) -> OptionsBuilder {
+        self.options.set_room_type_weight(room_type, weight);
+        return self;
+    }
+
+    pub fn room_type_size(mut self, room_type: RoomType, size: RoomSizeRange) -> OptionsBuilder {
+        self.options.set_room_type_size(room_type, Some(size));
+        return self;
+    }
+
+    pub fn generation_mode(mut self, generation_mode: GenerationMode) -> OptionsBuilder {
+        self.options.generation_mode = generation_mode;
+        return self;
+    }
+
+    pub fn cave_settings(mut self, cave_settings: CaveSettings) -> OptionsBuilder {
+        self.options.cave_settings = cave_settings;
+        return self;
+    }
+
+    pub fn tunnel_settings(mut self, tunnel_settings: TunnelSettings) -> OptionsBuilder {
+        self.options.tunnel_settings = tunnel_settings;
+        return self;
+    }
+
+    pub fn maze_settings(mut self, maze_settings: MazeSettings) -> OptionsBuilder {
+        self.options.maze_settings = maze_settings;
+        return self;
+    }
+
+    pub fn wfc_settings(mut self, wfc_settings: WfcSettings) -> OptionsBuilder {
+        self.options.wfc_settings = wfc_settings;
+        return self;
+    }
+
+    pub fn caverns(mut self, cavern_ratio: f64, cavern_method: CavernMethod) -> OptionsBuilder {
+        self.options.cavern_ratio = cavern_ratio;
+        self.options.cavern_method = cavern_method;
+        return self;
+    }
+
+    pub fn room_placement(mut self, room_placement: RoomPlacement) -> OptionsBuilder {
+        self.options.room_placement = room_placement;
+        return self;
+    }
+
+    pub fn min_room_distance(mut self, min_room_distance: usize) -> OptionsBuilder {
+        self.options.min_room_distance = min_room_distance;
+        return self;
+    }
+
+    pub fn placement_attempts(mut self, placement_attempts: usize) -> OptionsBuilder {
+        self.options.placement_attempts = placement_attempts;
+        return self;
+    }
+
+    pub fn connection_strategy(mut self, connection_strategy: ConnectionStrategy) -> OptionsBuilder {
+        self.options.connection_strategy = connection_strategy;
+        return self;
+    }
+
+    pub fn loop_ratio(mut self, loop_ratio: f64) -> OptionsBuilder {
+        self.options.loop_ratio = loop_ratio;
+        return self;
+    }
+
+    pub fn search_limit(mut self, search_limit: usize) -> OptionsBuilder {
+        self.options.search_limit = search_limit;
+        return self;
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> OptionsBuilder {
+        self.options.retry_policy = retry_policy;
+        return self;
+    }
+
+    pub fn prefab(mut self, placement: PrefabPlacement) -> OptionsBuilder {
+        self.options.prefabs.push(placement);
+        return self;
+    }
+
+    pub fn build(self) -> Result<Options, OptionsError> {
+        self.options.validate()?;
+        return Ok(self.options);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_retry_policy_is_used_by_builder() {
+        let policy = RetryPolicy::new_default();
+        assert_eq!((policy.limit_raises(), policy.partner_attempts(), policy.relax_room_borders()), (2, 64, true));
+        let options = OptionsBuilder::new().build().unwrap();
+        assert_eq!(options.retry_policy().limit_raises(), 2);
+        assert_eq!(options.retry_policy().partner_attempts(), 64);
+        assert!(options.retry_policy().relax_room_borders());
+    }
+
+    #[test]
+    fn disabled_retry_policy_has_no_retries() {
+        let policy = RetryPolicy::new_disabled();
+        assert_eq!((policy.limit_raises(), policy.partner_attempts(), policy.relax_room_borders()), (0, 0, false));
+    }
+
+    #[test]
+    fn parse_rule_reads_birth_and_survival() {
+        assert_eq!(CaveSettings::parse_rule("B678/S345678"), Ok((0b111000000, 0b111111000)));
+        assert_eq!(CaveSettings::parse_rule(" s23 / b3 "), Ok((0b1000, 0b1100)));
+        assert_eq!(CaveSettings::parse_rule("B/S"), Ok((0, 0)));
+    }
+
+    #[test]
+    fn parse_rule_rejects_invalid_rules() {
+        for rule in ["B5/B6", "S5/S6/B3", "B5", "B9/S4", "B5/S4x", "X5/S4", ""] {
+            assert_eq!(CaveSettings::parse_rule(rule), Err(OptionsError::InvalidCaveRule { rule: rule.to_string() }), "rule {}", rule);
+        }
+    }
+
+    #[test]
+    fn build_rejects_invalid_options() {
+        let caves = || OptionsBuilder::new().generation_mode(GenerationMode::Caves);
+        let tunnels = || OptionsBuilder::new().generation_mode(GenerationMode::Tunnels);
+        let cases: Vec<(OptionsBuilder, OptionsError)> = vec![
+            (OptionsBuilder::new().level_width(0), OptionsError::EmptyLevel { level_width: 0, level_height: 64 }),
+            (OptionsBuilder::new().border(0), OptionsError::ZeroBorder),
+            (OptionsBuilder::new().border(2).room_border(3), OptionsError::RoomBorderTooLarge { room_border: 3, border: 2 }),
+            (OptionsBuilder::new().room_types(Vec::new()), OptionsError::EmptyRoomTypes),
+            (OptionsBuilder::new().room_types(vec![RoomType::Prefab(2)]), OptionsError::PrefabRoomType { index: 2 }),
+            (OptionsBuilder::new().room_types(vec![RoomType::Cavern]), OptionsError::CavernRoomType),
+            (OptionsBuilder::new().room_types(vec![RoomType::Square]).room_type_weight(RoomType::Square, 0), OptionsError::ZeroRoomTypeWeights),
+            (OptionsBuilder::new().search_limit(0), OptionsError::ZeroSearchLimit),
+            (OptionsBuilder::new().connection_strategy(ConnectionStrategy::BspSiblings), OptionsError::BspConnectionWithoutBsp),
+            (OptionsBuilder::new().loop_ratio(1.5), OptionsError::InvalidLoopRatio { loop_ratio: 1.5 }),
+            (OptionsBuilder::new().caverns(-0.5, CavernMethod::DrunkardWalk), OptionsError::InvalidCavernRatio { cavern_ratio: -0.5 }),
+            (OptionsBuilder::new().prefab(PrefabPlacement::new(0, 3, 1, true)), OptionsError::InvalidPrefabCount { prefab: 0, min_count: 3, max_count: 1 }),
+            (OptionsBuilder::new().min_room_width(0), OptionsError::ZeroRoomSize),
+            (OptionsBuilder::new().min_room_width(9), OptionsError::InvalidRoomWidthRange { min_room_width: 9, max_room_width: 7 }),
+            (OptionsBuilder::new().min_room_height(9), OptionsError::InvalidRoomHeightRange { min_room_height: 9, max_room_height: 7 }),
+            (OptionsBuilder::new().room_type_size(RoomType::Square, RoomSizeRange::new(9, 5, 9, 5)), OptionsError::InvalidRoomWidthRange { min_room_width: 9, max_room_width: 5 }),
+            (OptionsBuilder::new().level_width(8), OptionsError::RoomWiderThanLevel { max_room_width: 7, level_width: 8, border: 1 }),
+            (OptionsBuilder::new().level_height(8), OptionsError::RoomHigherThanLevel { max_room_height: 7, level_height: 8, border: 1 }),
+            (caves().cave_settings(CaveSettings::new(1.5, 0, 0, 4, 0)), OptionsError::InvalidFillRatio { fill_ratio: 1.5 }),
+            (tunnels().tunnel_settings(TunnelSettings::new(0, 0.1, 100, 0.3, 0.1)), OptionsError::NoTunnelWalkers { walkers: 0, lifetime: 100 }),
+            (tunnels().tunnel_settings(TunnelSettings::new(4, 2.0, 100, 0.3, 0.1)), OptionsError::InvalidTurnProbability { turn_probability: 2.0 }),
+            (tunnels().tunnel_settings(TunnelSettings::new(4, 0.1, 100, 2.0, 0.1)), OptionsError::InvalidCoverage { coverage: 2.0 }),
+            (tunnels().tunnel_settings(TunnelSettings::new(4, 0.1, 100, 0.3, 2.0)), OptionsError::InvalidRoomProbability { room_probability: 2.0 }),
+            (OptionsBuilder::new().generation_mode(GenerationMode::Maze).maze_settings(MazeSettings::new(MazeAlgorithm::Prim, 2.0, 0)), OptionsError::InvalidBraidFactor { braid_factor: 2.0 }),
+            (OptionsBuilder::new().generation_mode(GenerationMode::Wfc).wfc_settings(WfcSettings::new(1, true, 10)), OptionsError::InvalidPatternSize { pattern_size: 1 })
+        ];
+        for (builder, expected) in cases {
+            assert_eq!(builder.build().unwrap_err(), expected);
+        }
+    }
+}
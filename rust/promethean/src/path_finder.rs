@@ -1,6 +1,8 @@
 use crate::level::point::Point;
 use crate::level::options::PathFinderTile;
 use std::fmt;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 
 #[derive(Debug)]
 pub struct PathFinderNode {
@@ -9,17 +11,21 @@ pub struct PathFinderNode {
     h: usize,
     parent_node_position: Point,
     f: usize,
-    closed: u8
+    closed: u8,
+    order: usize,
+    generation: u32
 }
 
 impl PathFinderNode{
     pub fn new(position: Point, g: usize, h: usize, parent_node_position: Point) -> PathFinderNode {
-        return PathFinderNode{ position: position,
-                               g: g,
-                               h: h,
-                               parent_node_position: parent_node_position,
+        return PathFinderNode{ position,
+                               g,
+                               h,
+                               parent_node_position,
                                f: 0,
-                               closed: 0};
+                               closed: 0,
+                               order: 0,
+                               generation: 0};
     }
 
     pub fn clone(&self) -> PathFinderNode {
@@ -28,7 +34,9 @@ impl PathFinderNode{
                                 h: self.h,
                                 parent_node_position: self.parent_node_position.clone(),
                                 f: self.f,
-                                closed: self.closed};
+                                closed: self.closed,
+                                order: self.order,
+                                generation: self.generation};
     }
 
     pub fn f(&self) -> usize{
@@ -53,6 +61,7 @@ impl PathFinderNode{
         self.parent_node_position.set(0, 0);
         self.f = 0;
         self.closed = 0;
+        self.order = 0;
     }
 
     pub fn update(&mut self, g: usize, h: usize, parent: Point) {
@@ -82,12 +91,7 @@ impl PathFinderNode{
     }
 
     pub fn is_undefined(&self) -> bool {
-        if self.closed != 0 {
-            return false;
-        }
-        else {
-            return true;
-        }
+        return self.closed == 0;
     }
 }
 
@@ -107,12 +111,19 @@ pub fn distance(start: &Point, end: &Point) -> usize{
     return x + y;
 }
 
+// entry of the open set: f value, the order in which the node was opened and node coordinates
+// the heap returns the node with the smallest f, from nodes with equal f it returns the node, which was opened first
+type OpenEntry = Reverse<(usize, usize, usize, usize)>;
+
 #[derive(Debug)]
 pub struct PathFinderGraph {
     height: usize,
     width: usize,
     internal_grid: Vec<Vec<PathFinderNode>>,
-    open: Vec<Point>
+    open: BinaryHeap<OpenEntry>,
+    open_count: usize,
+    opened_total: usize,
+    generation: u32
 }
 
 impl PathFinderGraph {
@@ -126,69 +137,101 @@ impl PathFinderGraph {
         internal_grid.push(x_array);
         }
 
-        let open = Vec::with_capacity(width);
+        let open = BinaryHeap::with_capacity(width);
 
-        return PathFinderGraph{ height, width, internal_grid, open};
+        return PathFinderGraph{ height, width, internal_grid, open, open_count: 0, opened_total: 0, generation: 0 };
     }
 
     pub fn get_node_from_grid(&self, x: usize, y: usize) -> &PathFinderNode {
         return &self.internal_grid[x][y];
     }
 
+    // return the node and reset it, if it was not touched since the last graph reset
+    fn node_mut(&mut self, x: usize, y: usize) -> &mut PathFinderNode {
+        let generation = self.generation;
+        let node: &mut PathFinderNode = &mut self.internal_grid[x][y];
+        if node.generation != generation {
+            node.reset();
+            node.generation = generation;
+        }
+        return node;
+    }
+
+    // nodes are reset lazily, when they are touched for the first time after the reset
     pub fn reset(&mut self) {
-        for x in 0.. self.height {
-            for y in 0.. self.width {
-                self.internal_grid[x][y].reset();
+        if self.generation == u32::MAX {
+            for x in 0.. self.height {
+                for y in 0.. self.width {
+                    self.internal_grid[x][y].reset();
+                    self.internal_grid[x][y].generation = 0;
+                }
             }
+            self.generation = 0;
+        }
+        else {
+            self.generation += 1;
         }
         self.open.clear();
+        self.open_count = 0;
+        self.opened_total = 0;
+    }
+
+    fn push_open(&mut self, x: usize, y: usize) {
+        let node: &PathFinderNode = &self.internal_grid[x][y];
+        self.open.push(Reverse((node.f(), node.order, x, y)));
     }
 
     pub fn open_node(&mut self, position: &Point, g: usize, h: usize, parent: &Point) {
-        let node: &mut PathFinderNode = &mut self.internal_grid[position.x() as usize][position.y() as usize];
+        let x = position.x() as usize;
+        let y = position.y() as usize;
+        let order = self.opened_total;
+        let node: &mut PathFinderNode = self.node_mut(x, y);
         node.update(g, h, Point::new(parent.x(), parent.y()));
         node.open();
+        node.order = order;
 
-        self.open.push(Point::new(node.position.x(), node.position.y()));
+        self.opened_total += 1;
+        self.open_count += 1;
+        self.push_open(x, y);
     }
 
     pub fn has_open_nodes(&self) -> bool {
-        return self.open.len() > 0;
+        return self.open_count > 0;
     }
 
     pub fn get_open_node_with_smallest_f(&mut self) -> PathFinderNode {
-        let zero_point: &Point = &self.open[0];
-        let mut to_return_i: usize = 0;
-        let mut to_return_f: usize = self.internal_grid[zero_point.x() as usize][zero_point.y() as usize].f();
-
-        for i in 0..self.open.len() {
-            let i_point: &Point = &self.open[i];
-            let node: &PathFinderNode = &self.internal_grid[i_point.x() as usize][i_point.y() as usize];
-            let node_f: usize = node.f();
-
-            if node_f < to_return_f {
-                to_return_i = i;
-                to_return_f = node_f;
+        // the heap may contain outdated entries for nodes with decreased f, skip them
+        while let Some(Reverse((f, _, x, y))) = self.open.pop() {
+            let node: &mut PathFinderNode = &mut self.internal_grid[x][y];
+            if node.is_closed() || node.f() != f {
+                continue;
             }
-        }
-
-        let zero_point = &self.open[to_return_i];
-        let mut return_node: PathFinderNode = self.internal_grid[zero_point.x() as usize][zero_point.y() as usize].clone();
-        self.open.remove(to_return_i);
 
-        return_node.close();
+            node.close();
+            self.open_count -= 1;
+            return node.clone();
+        }
 
-        return return_node;
+        panic!("get open node from the empty open set");
     }
 
     pub fn add_node(&mut self, x: usize, y: usize, g: usize, target: &Point, parent: &Point) {
-        let node: &mut PathFinderNode = &mut self.internal_grid[x][y];
+        let order = self.opened_total;
+        let node: &mut PathFinderNode = self.node_mut(x, y);
         let h = distance(node.position(), target);
         if !node.is_closed() {
+            let f = node.f();
             node.update(g, h, Point::new(parent.x(), parent.y()));
             if node.is_undefined() {
                 node.open();
-                self.open.push(Point::new(node.position.x(), node.position.y()));
+                node.order = order;
+                self.opened_total += 1;
+                self.open_count += 1;
+                self.push_open(x, y);
+            }
+            else if node.f() != f {
+                // lazy decrease of the key, the old entry will be skipped
+                self.push_open(x, y);
             }
         }
     }
@@ -214,22 +257,45 @@ pub struct PathFinder {
     search_limit: usize,
     height: usize,
     width: usize,
-    graph: PathFinderGraph
+    graph: PathFinderGraph,
+    blocked_points: Vec<Point>,
+    limit_reached: bool
 }
 
 impl PathFinder {
     pub fn new(world_grid: Vec<Vec<PathFinderTile>>, search_limit: usize) -> PathFinder {
         let height = world_grid.len();
         let width = world_grid[0].len();
-        return PathFinder { world_grid, search_limit, height, width, graph: PathFinderGraph::new(height, width)};
+        return PathFinder { world_grid, search_limit, height, width, graph: PathFinderGraph::new(height, width), blocked_points: Vec::new(), limit_reached: false };
     }
 
     pub fn block_point(&mut self, point: Point) {
         self.world_grid[point.x() as usize][point.y() as usize] = PathFinderTile::Blocked;
+        self.blocked_points.push(point);
+    }
+
+    // true if the last search was stopped by the search limit
+    pub fn limit_reached(&self) -> bool {
+        return self.limit_reached;
+    }
+
+    pub fn set_search_limit(&mut self, search_limit: usize) {
+        self.search_limit = search_limit;
+    }
+
+    // use another grid of the same size, all previously blocked points are blocked in the new grid too
+    // return the previous grid, so it can be used again later
+    pub fn replace_grid(&mut self, world_grid: Vec<Vec<PathFinderTile>>) -> Vec<Vec<PathFinderTile>> {
+        let previous = std::mem::replace(&mut self.world_grid, world_grid);
+        for point in &self.blocked_points {
+            self.world_grid[point.x() as usize][point.y() as usize] = PathFinderTile::Blocked;
+        }
+        return previous;
     }
 
     pub fn find_path(&mut self, start: &Point, end: &Point) -> Vec<Point> {
         let mut nodes_visited: usize = 0;
+        self.limit_reached = false;
         self.graph.reset();
         self.graph.open_node(start, 0, distance(start, end), start);
 
@@ -240,6 +306,7 @@ impl PathFinder {
             }
 
             if nodes_visited > self.search_limit {
+                self.limit_reached = true;
                 return Vec::new();
             }
 
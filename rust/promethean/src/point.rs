@@ -30,6 +30,7 @@ impl Point {
         self.y = y;
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn clone(&self) -> Point {
         return Point{ x: self.x, y: self.y };
     }
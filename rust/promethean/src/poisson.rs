@@ -0,0 +1,67 @@
+use super::preudo_random::PseudoRandom;
+
+// Bridson's algorithm: points inside [x_min, x_max) x [y_min, y_max), each pair of points is not closer than distance
+// each active point tries attempts random candidates in the ring between distance and 2 * distance around it
+pub fn poisson_disk_samples(random: &mut PseudoRandom, x_min: f64, x_max: f64, y_min: f64, y_max: f64, distance: f64, attempts: usize) -> Vec<(f64, f64)> {
+    let mut samples: Vec<(f64, f64)> = Vec::new();
+    if x_max <= x_min || y_max <= y_min || distance <= 0.0 {
+        return samples;
+    }
+
+    // each cell of the background grid contains at most one sample
+    let cell_size = distance / std::f64::consts::SQRT_2;
+    let grid_height = ((x_max - x_min) / cell_size).ceil() as usize;
+    let grid_width = ((y_max - y_min) / cell_size).ceil() as usize;
+    let mut grid: Vec<Option<usize>> = vec![None; grid_height * grid_width];
+    let cell = |p: (f64, f64)| -> (usize, usize) {
+        return ((((p.0 - x_min) / cell_size) as usize).min(grid_height - 1), (((p.1 - y_min) / cell_size) as usize).min(grid_width - 1));
+    };
+
+    let first = (x_min + random.next_float() * (x_max - x_min), y_min + random.next_float() * (y_max - y_min));
+    let (first_x, first_y) = cell(first);
+    grid[first_x * grid_width + first_y] = Some(0);
+    samples.push(first);
+    let mut active: Vec<usize> = vec![0];
+
+    while !active.is_empty() {
+        let active_index = random.next(0, active.len() - 1);
+        let origin = samples[active[active_index]];
+        let mut is_found = false;
+        for _ in 0..attempts {
+            let angle = random.next_float() * 2.0 * std::f64::consts::PI;
+            let radius = distance * (1.0 + random.next_float());
+            let candidate = (origin.0 + radius * angle.cos(), origin.1 + radius * angle.sin());
+            if candidate.0 < x_min || candidate.0 >= x_max || candidate.1 < y_min || candidate.1 >= y_max {
+                continue;
+            }
+
+            let (cell_x, cell_y) = cell(candidate);
+            let mut is_free = true;
+            for x in cell_x.saturating_sub(2)..(cell_x + 3).min(grid_height) {
+                for y in cell_y.saturating_sub(2)..(cell_y + 3).min(grid_width) {
+                    if let Some(s) = grid[x * grid_width + y] {
+                        let dx = samples[s].0 - candidate.0;
+                        let dy = samples[s].1 - candidate.1;
+                        if dx * dx + dy * dy < distance * distance {
+                            is_free = false;
+                        }
+                    }
+                }
+            }
+
+            if is_free {
+                grid[cell_x * grid_width + cell_y] = Some(samples.len());
+                active.push(samples.len());
+                samples.push(candidate);
+                is_found = true;
+                break;
+            }
+        }
+
+        if !is_found {
+            active.swap_remove(active_index);
+        }
+    }
+
+    return samples;
+}
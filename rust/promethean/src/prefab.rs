@@ -0,0 +1,160 @@
+use super::room_shape::RoomShape;
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+// characters of the prefab template
+pub const PREFAB_WALL: char = '#';
+pub const PREFAB_FLOOR: char = '.';
+pub const PREFAB_DOOR: char = 'D';
+pub const PREFAB_EMPTY: char = ' ';
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PrefabCell {
+    Empty,
+    Wall,
+    Floor,
+    Door
+}
+
+// hand-made room, defined by the ascii template
+// walls and empty cells are not floor, doors are floor tiles on the border of the template, corridors are connected only to doors
+#[derive(Debug, Clone)]
+pub struct Prefab {
+    height: usize,
+    width: usize,
+    cells: Vec<Vec<PrefabCell>>
+}
+
+impl Prefab {
+    // rows of the template are separated by new lines, empty lines at the start and at the end are ignored
+    // shorter rows are filled by empty cells
+    pub fn parse(template: &str) -> Result<Prefab, PrefabError> {
+        let lines: Vec<&str> = template.lines().map(|l| l.trim_end_matches('\r')).collect();
+        let start = lines.iter().position(|l| !l.trim().is_empty()).unwrap_or(lines.len());
+        let end = lines.iter().rposition(|l| !l.trim().is_empty()).map(|i| i + 1).unwrap_or(start);
+        let rows = &lines[start..end];
+        if rows.is_empty() {
+            return Err(PrefabError::Empty);
+        }
+
+        let height = rows.len();
+        let width = rows.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let mut cells: Vec<Vec<PrefabCell>> = vec![vec![PrefabCell::Empty; width]; height];
+        let mut is_floor = false;
+        let mut is_door = false;
+        for x in 0..height {
+            for (y, c) in rows[x].chars().enumerate() {
+                cells[x][y] = match c {
+                    PREFAB_WALL => PrefabCell::Wall,
+                    PREFAB_FLOOR => { is_floor = true; PrefabCell::Floor },
+                    PREFAB_DOOR => {
+                        if x != 0 && x != height - 1 && y != 0 && y != width - 1 {
+                            return Err(PrefabError::DoorNotOnBorder { row: x, column: y });
+                        }
+                        is_door = true;
+                        PrefabCell::Door
+                    },
+                    PREFAB_EMPTY => PrefabCell::Empty,
+                    _ => return Err(PrefabError::UnknownCharacter { row: x, column: y, character: c })
+                };
+            }
+        }
+
+        if !is_floor {
+            return Err(PrefabError::NoFloor);
+        }
+        if !is_door {
+            return Err(PrefabError::NoDoors);
+        }
+
+        return Ok(Prefab { height, width, cells });
+    }
+
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    fn mask(&self, cell_types: &[PrefabCell]) -> Vec<Vec<bool>> {
+        return self.cells.iter().map(|row| row.iter().map(|c| cell_types.contains(c)).collect()).collect();
+    }
+}
+
+impl RoomShape for Prefab {
+    // the size of the prefab is fixed, so the input size is ignored
+    fn floor_mask(&self, _height: usize, _width: usize) -> Vec<Vec<bool>> {
+        return self.mask(&[PrefabCell::Floor, PrefabCell::Door]);
+    }
+
+    fn door_mask(&self, _height: usize, _width: usize) -> Vec<Vec<bool>> {
+        return self.mask(&[PrefabCell::Door]);
+    }
+
+    fn is_square(&self) -> bool {
+        return false;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrefabError {
+    Empty,
+    UnknownCharacter { row: usize, column: usize, character: char },
+    DoorNotOnBorder { row: usize, column: usize },
+    NoFloor,
+    NoDoors
+}
+
+impl fmt::Display for PrefabError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            PrefabError::Empty => write!(f, "prefab template is empty"),
+            PrefabError::UnknownCharacter { row, column, character } => write!(f, "unknown character '{}' in prefab template at row {} column {}", character, row, column),
+            PrefabError::DoorNotOnBorder { row, column } => write!(f, "prefab door at row {} column {} should be on the border of the template", row, column),
+            PrefabError::NoFloor => write!(f, "prefab template should contain at least one floor tile"),
+            PrefabError::NoDoors => write!(f, "prefab template should contain at least one door")
+        };
+    }
+}
+
+impl std::error::Error for PrefabError {}
+
+impl From<PrefabError> for JsValue {
+    fn from(error: PrefabError) -> JsValue {
+        return JsError::new(&error.to_string()).into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_builds_masks() {
+        let prefab = Prefab::parse("\n###D\n#..#\n#.#\n").unwrap();
+        assert_eq!(prefab.height(), 3);
+        assert_eq!(prefab.width(), 4);
+        assert_eq!(prefab.floor_mask(0, 0), vec![
+            vec![false, false, false, true],
+            vec![false, true, true, false],
+            vec![false, true, false, false]
+        ]);
+        assert_eq!(prefab.door_mask(0, 0)[0], vec![false, false, false, true]);
+    }
+
+    #[test]
+    fn parse_rejects_door_not_on_border() {
+        assert_eq!(Prefab::parse("#D###\n#.D.#\n#####").unwrap_err(), PrefabError::DoorNotOnBorder { row: 1, column: 2 });
+    }
+
+    #[test]
+    fn parse_rejects_invalid_templates() {
+        assert_eq!(Prefab::parse(" \n\n").unwrap_err(), PrefabError::Empty);
+        assert_eq!(Prefab::parse("#D#\n#x#").unwrap_err(), PrefabError::UnknownCharacter { row: 1, column: 1, character: 'x' });
+        assert_eq!(Prefab::parse("#D#\n###").unwrap_err(), PrefabError::NoFloor);
+        assert_eq!(Prefab::parse("###\n#.#\n###").unwrap_err(), PrefabError::NoDoors);
+    }
+}
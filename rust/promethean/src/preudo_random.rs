@@ -15,6 +15,11 @@ impl PseudoRandom {
         return self.random.gen_range(in_min..=in_max);
     }
 
+    // uniform value in [0, 1)
+    pub fn next_float(&mut self) -> f64 {
+        return self.random.gen::<f64>();
+    }
+
     pub fn next_odd(&mut self, in_min: usize, in_max: usize) -> usize {
         let next_value: usize =self.next(in_min, in_max);
         if next_value % 2 != 0 {
@@ -1,12 +1,21 @@
 use crate::level::point::Point;
-use crate::level::options::{Options, RoomType, Tile};
+use crate::level::options::{Options, OptionsError, PrefabPlacement, RoomPlacement, RoomType, Tile};
 use super::preudo_random::PseudoRandom;
+use super::bsp::BspTree;
+use super::poisson::poisson_disk_samples;
+use super::cave_generator::generate_cavern;
+use super::room_shape::{RoomShape, RoomShapes, oriented_mask, oriented_door_mask, ORIENTATIONS_COUNT};
 use std::fmt;
 
 pub fn determine_max_position(level_dimension: usize, room_dimension: usize, border: usize) -> usize {
     return level_dimension - room_dimension - border;
 }
 
+// prefabs are placed further from the level border, so corridors can come to doors on any side
+fn prefab_border(options: &Options) -> usize {
+    return options.border() + options.room_border() + 1;
+}
+
 #[derive(Debug)]
 pub struct Room {
     height: usize,
@@ -14,7 +23,13 @@ pub struct Room {
     position: Point,
     room_center: Point,
     bottom_right: Point,
-    room_type: RoomType
+    room_type: RoomType,
+    orientation: usize,
+    floor: Vec<Vec<bool>>,
+    is_composite: bool,
+    entrance: Point,
+    doors: Vec<Point>,
+    leaf: Option<usize>
 }
 
 impl Room {
@@ -25,14 +40,133 @@ impl Room {
             position: Point::new(room_x as i32, room_y as i32),
             room_center: Point::new((room_x + room_height / 2) as i32, (room_y + room_width / 2) as i32),
             bottom_right: Point::new((room_x + room_height - 1) as i32, (room_y + room_width - 1) as i32),
-            room_type: room_type
+            room_type,
+            orientation: 0,
+            floor: Vec::new(),
+            is_composite: false,
+            entrance: Point::new((room_x + room_height / 2) as i32, (room_y + room_width / 2) as i32),
+            doors: Vec::new(),
+            leaf: None
         };
     }
 
+    // define floor tiles of the room and the point, where corridors are connected to the room
+    pub fn apply_shape(&mut self, shape: &dyn RoomShape) {
+        self.floor = oriented_mask(shape, self.height, self.width, self.orientation);
+        self.is_composite = shape.is_composite();
+        self.entrance = self.find_entrance();
+
+        let door_mask = oriented_door_mask(shape, self.height, self.width, self.orientation);
+        self.doors.clear();
+        for x in 0..door_mask.len() {
+            for y in 0..door_mask[x].len() {
+                if door_mask[x][y] {
+                    self.doors.push(Point::new(self.position.x() + x as i32, self.position.y() + y as i32));
+                }
+            }
+        }
+    }
+
+    // replace the floor of the room by the cave floor, which contains the center of the room
+    pub fn set_cavern(&mut self, floor: Vec<Vec<bool>>) {
+        self.room_type = RoomType::Cavern;
+        self.orientation = 0;
+        self.floor = floor;
+        // corridors can go through empty parts of the bounding box as for composite rooms
+        self.is_composite = true;
+        self.entrance = self.find_entrance();
+        self.doors.clear();
+    }
+
+    // the center of the room if it is a floor, otherwise the closest floor tile on the center row or column
+    fn find_entrance(&self) -> Point {
+        let x_offset = self.height / 2;
+        let y_offset = self.width / 2;
+        if self.is_floor(x_offset, y_offset) {
+            return self.room_center.clone();
+        }
+
+        for d in 1..self.height.max(self.width) {
+            let candidates = [(x_offset as i32 - d as i32, y_offset as i32), (x_offset as i32, y_offset as i32 - d as i32),
+                              (x_offset as i32 + d as i32, y_offset as i32), (x_offset as i32, y_offset as i32 + d as i32)];
+            for (x, y) in candidates {
+                if x >= 0 && y >= 0 && self.is_floor(x as usize, y as usize) {
+                    return Point::new(self.position.x() + x, self.position.y() + y);
+                }
+            }
+        }
+        return self.room_center.clone();
+    }
+
+    // offsets are from the top left corner of the room
+    pub fn is_floor(&self, x_offset: usize, y_offset: usize) -> bool {
+        // the mask of the custom shape can be smaller than the room
+        return self.floor.get(x_offset).and_then(|row| row.get(y_offset)).copied().unwrap_or(false);
+    }
+
+    pub fn entrance(&self) -> &Point {
+        return &self.entrance;
+    }
+
+    pub fn doors(&self) -> &Vec<Point> {
+        return &self.doors;
+    }
+
+    // the point, where the corridor to the target is connected, for rooms with doors it is the closest door
+    pub fn entrance_towards(&self, target: &Point) -> &Point {
+        let mut closest: &Point = &self.entrance;
+        let mut closest_distance = i32::MAX;
+        for door in &self.doors {
+            let distance = (door.x() - target.x()).abs() + (door.y() - target.y()).abs();
+            if distance < closest_distance {
+                closest = door;
+                closest_distance = distance;
+            }
+        }
+        return closest;
+    }
+
+    // the direction from the door to the outside of the room, doors are always on the border of the bounding box
+    pub fn door_direction(&self, door: &Point) -> (i32, i32) {
+        if door.x() == self.position.x() {
+            return (-1, 0);
+        }
+        else if door.x() == self.bottom_right.x() {
+            return (1, 0);
+        }
+        else if door.y() == self.position.y() {
+            return (0, -1);
+        }
+        return (0, 1);
+    }
+
     pub fn room_type(&self) -> RoomType {
         return self.room_type;
     }
 
+    // rotation and mirroring of the room shape, see room_shape::oriented_mask
+    pub fn orientation(&self) -> usize {
+        return self.orientation;
+    }
+
+    pub fn set_orientation(&mut self, orientation: usize) {
+        self.orientation = orientation;
+    }
+
+    // the index of the BSP tree leaf, which contains the room
+    pub fn leaf(&self) -> Option<usize> {
+        return self.leaf;
+    }
+
+    pub fn set_leaf(&mut self, leaf: Option<usize>) {
+        self.leaf = leaf;
+    }
+
+    // if true, then only floor tiles of the room are blocked for corridors
+    pub fn is_composite(&self) -> bool {
+        return self.is_composite;
+    }
+
     pub fn height(&self) -> usize {
         return self.height;
     }
@@ -65,67 +199,20 @@ impl Room {
         return true;
     }
 
+    pub fn floor_mask(&self) -> Vec<Vec<bool>> {
+        return (0..self.height).map(|x| (0..self.width).map(|y| self.is_floor(x, y)).collect()).collect();
+    }
+
     pub fn get_tiles(&self) -> Vec<Vec<Tile>> {
-        match self.room_type {
-            RoomType::Diamond => {
-                let mut offset: i32 = 0;
-                let y_middle: i32 = (self.width / 2) as i32;
-                
-                let mut arr: Vec<Vec<Tile>> = Vec::with_capacity(self.height);
-                for x in 0..self.height {
-                    let mut arr_x: Vec<Tile> = Vec::with_capacity(self.width);
-                    for y in 0..self.width {
-                        let y_int = y as i32;
-                        if y_int < y_middle - offset || y_int >= y_middle + offset + 1 {
-                            arr_x.push(Tile::Empty);
-                        }
-                        else {
-                            arr_x.push(Tile::Floor);
-                        }
-                    }
-                    offset =  if x < self.height / 2 { offset + 1 } else { offset - 1 };
-                    arr.push(arr_x);
-                }
-                return arr;
-            },
-            RoomType::Cross => {
-                let mut arr: Vec<Vec<Tile>> = Vec::with_capacity(self.height);
-
-                let height_float = self.height as f64;
-                let width_float = self.width as f64;
-                for x in 0..self.height {
-                    let mut arr_x: Vec<Tile> = Vec::with_capacity(self.width);
-                    let x_float = x as f64;
-                    for y in 0..self.width {
-                        let y_float = y as f64;
-                        let x_lower_bound = height_float * 0.333 - 1.0;
-                        let x_upper_bound = height_float * 0.666;
-
-                        let y_lower_bound = width_float * 0.333 - 1.0;
-                        let y_upper_bound = width_float * 0.666;
-                        if (x_float > x_lower_bound && x_float < x_upper_bound) || (y_float > y_lower_bound && y_float < y_upper_bound) {
-                            arr_x.push(Tile::Floor);
-                        }
-                        else {
-                            arr_x.push(Tile::Empty);
-                        }
-                    }
-                    arr.push(arr_x);
-                }
-                return arr;
-            },
-            _ => {
-                let mut arr: Vec<Vec<Tile>> = Vec::with_capacity(self.height);
-                for _x in 0..self.height {
-                    let mut arr_x: Vec<Tile> = Vec::with_capacity(self.width);
-                    for _y in 0..self.width {
-                        arr_x.push(Tile::Floor);
-                    }
-                    arr.push(arr_x);
-                }
-                return arr;
+        let mut arr: Vec<Vec<Tile>> = Vec::with_capacity(self.height);
+        for x in 0..self.height {
+            let mut arr_x: Vec<Tile> = Vec::with_capacity(self.width);
+            for y in 0..self.width {
+                arr_x.push(if self.is_floor(x, y) { Tile::Floor } else { Tile::Empty });
             }
-        };
+            arr.push(arr_x);
+        }
+        return arr;
     }
 }
 
@@ -145,26 +232,154 @@ impl RoomGenerator {
         return RoomGenerator{ random: in_random };
     }
 
-    fn generate(&mut self, options : &Options) -> Option<Room> {
+    // the same random stream is used by other generators, so each call of generate gives the new level
+    pub fn random_mut(&mut self) -> &mut PseudoRandom {
+        return &mut self.random;
+    }
+
+    // weighted random choice, with equal weights it is the same as the uniform choice
+    fn select_room_type(&mut self, options: &Options) -> RoomType {
         let room_types = options.room_types();
-        let room_type: RoomType = room_types[self.random.next(1, room_types.len()) - 1];
-        let room_width = self.random.next_odd(options.min_room_width(), options.max_room_width());
-        let room_height = match room_type {
-            RoomType::Rectangle => self.random.next_odd(options.min_room_height(), options.max_room_height()),
-            _ => room_width
-        };
+        let weights: Vec<usize> = room_types.iter().map(|&t| options.room_type_weight(t)).collect();
+        let mut value = self.random.next(1, weights.iter().sum());
+        for i in 0..room_types.len() {
+            if value <= weights[i] {
+                return room_types[i];
+            }
+            value -= weights[i];
+        }
+        return room_types[room_types.len() - 1];
+    }
+
+    // odd random size from the range of the room type, square shapes have the same height and width
+    fn generate_room_size(&mut self, options: &Options, shapes: &RoomShapes, room_type: RoomType) -> (usize, usize) {
+        let size = options.room_type_size(room_type);
+        let room_width = self.random.next_odd(size.min_width(), size.max_width());
+        let room_height = if shapes.get(room_type).is_square() { room_width } else { self.random.next_odd(size.min_height(), size.max_height()) };
+        return (room_height, room_width);
+    }
+
+    // the size, which is smaller by 2 tiles, square shapes are shrunk in both directions, other shapes along the larger side
+    // None, if the room already has the minimal size
+    fn shrink_room_size(&self, options: &Options, shapes: &RoomShapes, room_type: RoomType, room_height: usize, room_width: usize) -> Option<(usize, usize)> {
+        let size = options.room_type_size(room_type);
+        let can_shrink_width = room_width >= size.min_width() + 2;
+        let can_shrink_height = room_height >= size.min_height() + 2;
+        if shapes.get(room_type).is_square() {
+            if can_shrink_width {
+                return Some((room_width - 2, room_width - 2));
+            }
+        }
+        else if can_shrink_height && (room_height >= room_width || !can_shrink_width) {
+            return Some((room_height - 2, room_width));
+        }
+        else if can_shrink_width {
+            return Some((room_height, room_width - 2));
+        }
+        return None;
+    }
+
+    // the room with the given type and size at the position of the other room, moved inside the level, if it is too large
+    fn room_at_anchor(&mut self, options: &Options, shapes: &RoomShapes, anchor: &Room, room_type: RoomType, room_height: usize, room_width: usize) -> Option<Room> {
+        let max_x = determine_max_position(options.level_height(), room_height, options.border());
+        let max_y = determine_max_position(options.level_width(), room_width, options.border());
+        if max_x < options.border() || max_y < options.border() {
+            return None;
+        }
+        let room_x = (anchor.position().x() as usize).clamp(options.border(), max_x);
+        let room_y = (anchor.position().y() as usize).clamp(options.border(), max_y);
+        let mut room = Room::new(room_height, room_width, room_x, room_y, room_type);
+        if room_type == anchor.room_type() {
+            room.set_orientation(anchor.orientation());
+        }
+        else if shapes.get(room_type).is_composite() {
+            room.set_orientation(self.random.next(0, ORIENTATIONS_COUNT - 1));
+        }
+        return Some(room);
+    }
+
+    // the next candidate after the failed room, attempts cycle through shrinking the room, selecting the new room type at the same place and the new random room
+    fn retry_room(&mut self, options: &Options, shapes: &RoomShapes, failed: &Room, attempt: usize) -> Option<Room> {
+        if attempt % 3 == 1 {
+            if let Some((room_height, room_width)) = self.shrink_room_size(options, shapes, failed.room_type(), failed.height(), failed.width()) {
+                return self.room_at_anchor(options, shapes, failed, failed.room_type(), room_height, room_width);
+            }
+        }
+        if attempt % 3 != 0 {
+            let room_type = self.select_room_type(options);
+            let (room_height, room_width) = self.generate_room_size(options, shapes, room_type);
+            return self.room_at_anchor(options, shapes, failed, room_type, room_height, room_width);
+        }
+        return self.generate(options, shapes);
+    }
+
+    fn generate(&mut self, options : &Options, shapes: &RoomShapes) -> Option<Room> {
+        let room_type = self.select_room_type(options);
+        let (room_height, room_width) = self.generate_room_size(options, shapes, room_type);
         let max_x = determine_max_position(options.level_height(), room_height, options.border());
         let max_y = determine_max_position(options.level_width(), room_width, options.border());
         if max_x >= options.border() && max_y >= options.border() {
             let room_x = self.random.next(options.border(), max_x);
             let room_y = self.random.next(options.border(), max_y);
-            return Some(Room::new(room_height, room_width, room_x, room_y, room_type));
+            let mut room = Room::new(room_height, room_width, room_x, room_y, room_type);
+            if shapes.get(room_type).is_composite() {
+                room.set_orientation(self.random.next(0, ORIENTATIONS_COUNT - 1));
+            }
+            return Some(room);
         }
         else{
             return Option::None;
         }
     }
 
+    fn generate_prefab(&mut self, options: &Options, shapes: &RoomShapes, placement: &PrefabPlacement) -> Option<Room> {
+        let prefab = shapes.prefab(placement.prefab())?;
+        let orientation = if placement.allow_rotation() { self.random.next(0, ORIENTATIONS_COUNT - 1) } else { 0 };
+        let (room_height, room_width) = if orientation & 4 != 0 { (prefab.width(), prefab.height()) } else { (prefab.height(), prefab.width()) };
+        let border = prefab_border(options);
+        if room_height + 2 * border > options.level_height() || room_width + 2 * border > options.level_width() {
+            return None;
+        }
+
+        let max_x = determine_max_position(options.level_height(), room_height, border);
+        let max_y = determine_max_position(options.level_width(), room_width, border);
+        let room_x = self.random.next(border, max_x);
+        let room_y = self.random.next(border, max_y);
+        let mut room = Room::new(room_height, room_width, room_x, room_y, RoomType::Prefab(placement.prefab()));
+        room.set_orientation(orientation);
+        return Some(room);
+    }
+
+    // prefabs are placed before other rooms and never overlap each other
+    fn generate_prefabs(&mut self, options: &Options, shapes: &RoomShapes) -> Result<Vec<Room>, OptionsError> {
+        let mut rooms: Vec<Room> = Vec::new();
+        for placement in options.prefabs() {
+            let count = self.random.next(placement.min_count(), placement.max_count());
+            let mut placed: usize = 0;
+            for _ in 0..count {
+                let room = match self.generate_prefab(options, shapes, placement) {
+                    Some(r) => r,
+                    None => break
+                };
+                if self.is_intersections(&rooms, &room, options.room_border()) {
+                    match self.reposition(&rooms, &room, options, prefab_border(options)) {
+                        Some(r) => rooms.push(r),
+                        None => break
+                    }
+                }
+                else {
+                    rooms.push(room);
+                }
+                placed += 1;
+            }
+
+            if placed < placement.min_count() {
+                return Err(OptionsError::PrefabNotPlaced { prefab: placement.prefab(), placed, min_count: placement.min_count() });
+            }
+        }
+        return Ok(rooms);
+    }
+
     fn is_intersections(&self, rooms: &Vec<Room>, target: &Room, border: usize) -> bool {
         for room in rooms {
             if target.intersects(room, border) {
@@ -174,11 +389,11 @@ impl RoomGenerator {
         return false;
     }
 
-    fn reposition(&self, rooms: &Vec<Room>, room: &Room, options: &Options) -> Option<Room> {
-        let min_x: usize = options.border();
-        let min_y: usize = options.border();
-        let max_x: usize = determine_max_position(options.level_height(), room.height(), options.border());
-        let max_y: usize = determine_max_position(options.level_width(), room.width(), options.border());
+    fn reposition(&self, rooms: &Vec<Room>, room: &Room, options: &Options, border: usize) -> Option<Room> {
+        let min_x: usize = border;
+        let min_y: usize = border;
+        let max_x: usize = determine_max_position(options.level_height(), room.height(), border);
+        let max_y: usize = determine_max_position(options.level_width(), room.width(), border);
 
         let lower_bound = Point::new(min_x as i32, min_y as i32);
         let upper_bound = Point::new(max_x as i32, max_y as i32);
@@ -266,7 +481,7 @@ impl RoomGenerator {
             }
             else if direction == 3 {
                 if bottom_left.y() >= lower_bound.y() {
-                    while direction_iterator > top_left.x() && direction_iterator >= lower_bound.x() + 1 {
+                    while direction_iterator > top_left.x() && direction_iterator > lower_bound.x() {
                         if direction_iterator > upper_bound.x() {
                             direction_iterator -= 1;
                         }
@@ -320,7 +535,8 @@ impl RoomGenerator {
             else {
                 match position {
                     Some(p) => {
-                        let new_room_candidate = Room::new(room.height(), room.width(), p.x() as usize, p.y() as usize, room.room_type());
+                        let mut new_room_candidate = Room::new(room.height(), room.width(), p.x() as usize, p.y() as usize, room.room_type());
+                        new_room_candidate.set_orientation(room.orientation());
                         if !self.is_intersections(rooms, &new_room_candidate, options.room_border()) {
                             return Option::Some(new_room_candidate);
                         }
@@ -333,53 +549,350 @@ impl RoomGenerator {
         return Option::None;
     }
 
-    fn generate_overlapping_rooms(&mut self, options: &Options) -> Vec<Room> {
-        let mut rooms: Vec<Room> = Vec::with_capacity(options.number_of_rooms());
-        for _ in 0..options.number_of_rooms() {
-            let new_room = self.generate(options);
+    // rooms, which overlap prefabs, are skipped
+    // each skipped room spends one of placement_attempts additional tries
+    fn generate_overlapping_rooms(&mut self, options: &Options, shapes: &RoomShapes, prefabs: Vec<Room>) -> Vec<Room> {
+        let prefabs_count = prefabs.len();
+        let mut rooms: Vec<Room> = prefabs;
+        rooms.reserve(options.number_of_rooms());
+        for _ in 0..(options.number_of_rooms() + options.placement_attempts()) {
+            if rooms.len() - prefabs_count >= options.number_of_rooms() {
+                break;
+            }
+            let new_room = self.generate(options, shapes);
             match new_room {
-                Some(room) => rooms.push(room),
-                None => {}
+                Some(room) if !rooms[..prefabs_count].iter().any(|p| room.intersects(p, options.room_border())) => rooms.push(room),
+                _ => {}
             };
         }
 
         return rooms;
     }
 
-    fn generate_non_overlapping_rooms(&mut self, options: &Options) -> Vec<Room> {
-        let mut rooms: Vec<Room> = Vec::with_capacity(options.number_of_rooms());
+    fn generate_non_overlapping_rooms(&mut self, options: &Options, shapes: &RoomShapes, prefabs: Vec<Room>) -> Vec<Room> {
+        if options.placement_attempts() > 0 {
+            return self.fill_non_overlapping_rooms(options, shapes, prefabs);
+        }
+
+        let mut rooms: Vec<Room> = prefabs;
+        rooms.reserve(options.number_of_rooms());
         for _ in 0..options.number_of_rooms() {
-            let new_room = self.generate(options);
-            match new_room {
-                Some(room) => {
-                    if self.is_intersections(&rooms, &room, options.room_border()){
-                        let repositioned_room = self.reposition(&rooms, &room, options);
-                        match repositioned_room {
-                            Some(r) => {
-                                rooms.push(r);
-                            },
-                            None => {
-                                break;
-                            }
+            let new_room = self.generate(options, shapes);
+            if let Some(room) = new_room {
+                if self.is_intersections(&rooms, &room, options.room_border()){
+                    let repositioned_room = self.reposition(&rooms, &room, options, options.border());
+                    match repositioned_room {
+                        Some(r) => {
+                            rooms.push(r);
+                        },
+                        None => {
+                            break;
                         }
                     }
-                    else {
+                }
+                else {
+                    rooms.push(room);
+                }
+            };
+        }
+
+        return rooms;
+    }
+
+    // place rooms until there are number_of_rooms of them, placement_attempts failures or the level is full
+    // after each failure the next candidate is selected by retry_room
+    // the room type is full, when the room of its minimal size does not fit anywhere, rooms are only added, so it stays full
+    // rooms of full types are not searched in the level, the level is full, when all selectable types are full
+    fn fill_non_overlapping_rooms(&mut self, options: &Options, shapes: &RoomShapes, prefabs: Vec<Room>) -> Vec<Room> {
+        let mut rooms: Vec<Room> = prefabs;
+        rooms.reserve(options.number_of_rooms());
+        let mut placed: usize = 0;
+        let mut failures: usize = 0;
+        let mut full_types: Vec<RoomType> = Vec::new();
+        let mut candidate = self.generate(options, shapes);
+        while placed < options.number_of_rooms() {
+            let failed = match candidate {
+                Some(room) if full_types.contains(&room.room_type()) => Some(room),
+                Some(room) => {
+                    if !self.is_intersections(&rooms, &room, options.room_border()) {
                         rooms.push(room);
+                        placed += 1;
+                        candidate = self.generate(options, shapes);
+                        continue;
+                    }
+                    match self.reposition(&rooms, &room, options, options.border()) {
+                        Some(r) => {
+                            rooms.push(r);
+                            placed += 1;
+                            candidate = self.generate(options, shapes);
+                            continue;
+                        },
+                        None => {
+                            if self.reposition(&rooms, &self.smallest_room(options, shapes, &room), options, options.border()).is_none() {
+                                full_types.push(room.room_type());
+                            }
+                            Some(room)
+                        }
                     }
                 },
-                None => {}
+                None => None
+            };
+
+            failures += 1;
+            if failures > options.placement_attempts() || options.room_types().iter().all(|t| options.room_type_weight(*t) == 0 || full_types.contains(t)) {
+                break;
+            }
+            candidate = match failed {
+                Some(room) => self.retry_room(options, shapes, &room, failures),
+                None => self.generate(options, shapes)
             };
         }
 
         return rooms;
     }
 
-    pub fn generate_rooms(&mut self, options: &Options) -> Vec<Room> {
-        if options.overlap_rooms() {
-            return self.generate_overlapping_rooms(options);
+    // the room of the same type and orientation with the minimal size
+    fn smallest_room(&self, options: &Options, shapes: &RoomShapes, room: &Room) -> Room {
+        let size = options.room_type_size(room.room_type());
+        let min_width = size.min_width();
+        let min_height = if shapes.get(room.room_type()).is_square() { min_width } else { size.min_height() };
+        let mut smallest = Room::new(min_height, min_width, room.position().x() as usize, room.position().y() as usize, room.room_type());
+        smallest.set_orientation(room.orientation());
+        return smallest;
+    }
+
+    // split the level until there are number_of_rooms leaves or no leaf can contain two rooms
+    // the largest leaf is split first, the split is across the longer side
+    fn generate_bsp_tree(&mut self, options: &Options, shapes: &RoomShapes) -> BspTree {
+        let margin = options.room_border() + 1;
+        let mut min_width = usize::MAX;
+        let mut min_height = usize::MAX;
+        for &room_type in options.room_types() {
+            let size = options.room_type_size(room_type);
+            min_width = min_width.min(size.min_width());
+            min_height = min_height.min(if shapes.get(room_type).is_square() { size.min_width() } else { size.min_height() });
+        }
+        let min_leaf_height = min_height + 2 * margin;
+        let min_leaf_width = min_width + 2 * margin;
+
+        let mut tree = BspTree::new(Point::new(options.border() as i32, options.border() as i32),
+                                    options.level_height() - 2 * options.border(),
+                                    options.level_width() - 2 * options.border());
+        let mut leaves_count: usize = 1;
+        while leaves_count < options.number_of_rooms() {
+            let mut candidate: Option<usize> = None;
+            for leaf in tree.leaves() {
+                let node = &tree.nodes()[leaf];
+                if node.height() < 2 * min_leaf_height && node.width() < 2 * min_leaf_width {
+                    continue;
+                }
+                let is_larger = match candidate {
+                    Some(c) => node.height() * node.width() > tree.nodes()[c].height() * tree.nodes()[c].width(),
+                    None => true
+                };
+                if is_larger {
+                    candidate = Some(leaf);
+                }
+            }
+
+            let leaf = match candidate {
+                Some(l) => l,
+                None => break
+            };
+            let height = tree.nodes()[leaf].height();
+            let width = tree.nodes()[leaf].width();
+            let can_split_rows = height >= 2 * min_leaf_height;
+            let can_split_columns = width >= 2 * min_leaf_width;
+            let is_horizontal = if can_split_rows && can_split_columns {
+                if height * 4 > width * 5 { true } else if width * 4 > height * 5 { false } else { self.random.next(0, 1) == 0 }
+            }
+            else {
+                can_split_rows
+            };
+            let offset = if is_horizontal { self.random.next(min_leaf_height, height - min_leaf_height) } else { self.random.next(min_leaf_width, width - min_leaf_width) };
+            tree.split(leaf, is_horizontal, offset);
+            leaves_count += 1;
+        }
+        return tree;
+    }
+
+    // one room in each leaf of the BSP tree, rooms are not closer than room_border + 1 to the leaf sides
+    // leaves, where the room of the selected type does not fit, stay empty, as well as rooms which intersect prefabs
+    fn generate_bsp_rooms(&mut self, options: &Options, shapes: &RoomShapes, prefabs: Vec<Room>) -> (Vec<Room>, BspTree) {
+        let tree = self.generate_bsp_tree(options, shapes);
+        let margin = options.room_border() + 1;
+        let prefabs_count = prefabs.len();
+        let mut rooms: Vec<Room> = prefabs;
+        for leaf in tree.leaves() {
+            let node = &tree.nodes()[leaf];
+            let available_height = node.height().saturating_sub(2 * margin);
+            let available_width = node.width().saturating_sub(2 * margin);
+            let room_type = self.select_room_type(options);
+            let size = options.room_type_size(room_type);
+            let is_square = shapes.get(room_type).is_square();
+            let width_limit = if is_square { available_width.min(available_height) } else { available_width };
+            if size.min_width() > width_limit || (!is_square && size.min_height() > available_height) {
+                continue;
+            }
+
+            let room_width = self.random.next_odd(size.min_width(), size.max_width().min(width_limit)).min(width_limit);
+            let room_height = if is_square { room_width } else { self.random.next_odd(size.min_height(), size.max_height().min(available_height)).min(available_height) };
+            let x_start = node.position().x() as usize + margin;
+            let y_start = node.position().y() as usize + margin;
+            let room_x = self.random.next(x_start, x_start + available_height - room_height);
+            let room_y = self.random.next(y_start, y_start + available_width - room_width);
+            let mut room = Room::new(room_height, room_width, room_x, room_y, room_type);
+            if shapes.get(room_type).is_composite() {
+                room.set_orientation(self.random.next(0, ORIENTATIONS_COUNT - 1));
+            }
+            room.set_leaf(Some(leaf));
+            if !rooms[..prefabs_count].iter().any(|p| room.intersects(p, options.room_border())) {
+                rooms.push(room);
+            }
         }
-        else {
-            return self.generate_non_overlapping_rooms(options);
+        return (rooms, tree);
+    }
+
+    // by default the distance is a bit larger than the largest room, larger rooms, which do not fit between samples, are shrunk
+    fn poisson_distance(&self, options: &Options) -> f64 {
+        if options.min_room_distance() > 0 {
+            return options.min_room_distance() as f64;
+        }
+
+        let mut max_size: usize = 0;
+        for &room_type in options.room_types() {
+            let size = options.room_type_size(room_type);
+            max_size = max_size.max(size.max_width()).max(size.max_height());
+        }
+        return (max_size + options.room_border() + 1) as f64;
+    }
+
+    // the room with the center at the sample, moved inside the level, if it is close to the border
+    #[allow(clippy::too_many_arguments)]
+    fn room_at_sample(&self, options: &Options, room_type: RoomType, orientation: usize, room_height: usize, room_width: usize, sample_x: f64, sample_y: f64) -> Option<Room> {
+        if room_height + 2 * options.border() > options.level_height() || room_width + 2 * options.border() > options.level_width() {
+            return None;
+        }
+        let max_x = determine_max_position(options.level_height(), room_height, options.border());
+        let max_y = determine_max_position(options.level_width(), room_width, options.border());
+        let room_x = (sample_x as usize).saturating_sub(room_height / 2).clamp(options.border(), max_x);
+        let room_y = (sample_y as usize).saturating_sub(room_width / 2).clamp(options.border(), max_y);
+        let mut room = Room::new(room_height, room_width, room_x, room_y, room_type);
+        room.set_orientation(orientation);
+        return Some(room);
+    }
+
+    // rooms are centered at Poisson-disk samples, samples are used in random order, so rooms cover the whole level even if there are more samples than rooms
+    // samples, where the room does not fit, are skipped
+    // with the automatic distance the rest of rooms is placed at samples with smaller distances, each time the distance is decreased by the quarter
+    fn generate_poisson_rooms(&mut self, options: &Options, shapes: &RoomShapes, prefabs: Vec<Room>) -> Vec<Room> {
+        let mut distance = self.poisson_distance(options);
+        let mut rooms: Vec<Room> = prefabs;
+        let mut placed = self.place_poisson_samples(options, shapes, &mut rooms, distance, options.number_of_rooms());
+        if options.min_room_distance() > 0 {
+            return rooms;
+        }
+
+        while placed < options.number_of_rooms() && distance * 0.75 >= 1.0 {
+            distance *= 0.75;
+            placed += self.place_poisson_samples(options, shapes, &mut rooms, distance, options.number_of_rooms() - placed);
+        }
+        return rooms;
+    }
+
+    // the sample position is truncated and the room is moved inside the level, so the distance is checked for the final center of the room
+    fn is_far_from_centers(&self, rooms: &[Room], room: &Room, distance: f64) -> bool {
+        let center = room.center();
+        return rooms.iter().all(|r| {
+            let dx = (r.center().x() - center.x()) as f64;
+            let dy = (r.center().y() - center.y()) as f64;
+            return dx * dx + dy * dy >= distance * distance;
+        });
+    }
+
+    // return the number of placed rooms
+    fn place_poisson_samples(&mut self, options: &Options, shapes: &RoomShapes, rooms: &mut Vec<Room>, distance: f64, count: usize) -> usize {
+        let border = options.border() as f64;
+        let mut samples = poisson_disk_samples(&mut self.random, border, options.level_height() as f64 - border, border, options.level_width() as f64 - border, distance, 30);
+        for i in (1..samples.len()).rev() {
+            let j = self.random.next(0, i);
+            samples.swap(i, j);
+        }
+
+        let mut placed: usize = 0;
+        for (sample_x, sample_y) in samples {
+            if placed >= count {
+                break;
+            }
+
+            let room_type = self.select_room_type(options);
+            let (mut room_height, mut room_width) = self.generate_room_size(options, shapes, room_type);
+            let orientation = if shapes.get(room_type).is_composite() { self.random.next(0, ORIENTATIONS_COUNT - 1) } else { 0 };
+            // the room, which intersects other rooms, is shrunk around the sample until it fits or reaches the minimal size
+            loop {
+                match self.room_at_sample(options, room_type, orientation, room_height, room_width, sample_x, sample_y) {
+                    Some(room) if !self.is_intersections(rooms, &room, options.room_border()) && self.is_far_from_centers(rooms, &room, distance) => {
+                        rooms.push(room);
+                        placed += 1;
+                        break;
+                    },
+                    _ => {}
+                }
+
+                match self.shrink_room_size(options, shapes, room_type, room_height, room_width) {
+                    Some((h, w)) => {
+                        room_height = h;
+                        room_width = w;
+                    },
+                    None => break
+                }
+            }
+        }
+        return placed;
+    }
+
+    // turn the cavern_ratio part of generated rooms into caverns, prefabs are not changed
+    fn generate_caverns(&mut self, options: &Options, rooms: &mut [Room]) {
+        if options.cavern_ratio() <= 0.0 {
+            return;
+        }
+
+        let mut candidates: Vec<usize> = (0..rooms.len()).filter(|&i| !matches!(rooms[i].room_type(), RoomType::Prefab(_))).collect();
+        let count = (options.cavern_ratio() * candidates.len() as f64).round() as usize;
+        for i in 0..count {
+            let j = self.random.next(i, candidates.len() - 1);
+            candidates.swap(i, j);
+            let room = &mut rooms[candidates[i]];
+            let floor = generate_cavern(&mut self.random, room.height(), room.width(), options.cave_settings(), options.cavern_method());
+            room.set_cavern(floor);
+        }
+    }
+
+    // return rooms and the BSP tree, if rooms are placed by the BSP
+    pub fn generate_rooms(&mut self, options: &Options, shapes: &RoomShapes) -> Result<(Vec<Room>, Option<BspTree>), OptionsError> {
+        let prefabs = self.generate_prefabs(options, shapes)?;
+        let mut bsp_tree: Option<BspTree> = None;
+        let mut rooms = match options.room_placement() {
+            RoomPlacement::Random => {
+                if options.overlap_rooms() {
+                    self.generate_overlapping_rooms(options, shapes, prefabs)
+                }
+                else {
+                    self.generate_non_overlapping_rooms(options, shapes, prefabs)
+                }
+            },
+            RoomPlacement::Bsp => {
+                let (bsp_rooms, tree) = self.generate_bsp_rooms(options, shapes, prefabs);
+                bsp_tree = Some(tree);
+                bsp_rooms
+            },
+            RoomPlacement::Poisson => self.generate_poisson_rooms(options, shapes, prefabs)
+        };
+
+        for room in rooms.iter_mut() {
+            room.apply_shape(shapes.get(room.room_type()));
         }
+        self.generate_caverns(options, &mut rooms);
+        return Ok((rooms, bsp_tree));
     }
 }
\ No newline at end of file
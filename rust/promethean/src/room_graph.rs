@@ -0,0 +1,233 @@
+use crate::level::point::Point;
+use std::collections::VecDeque;
+
+pub fn squared_distance(a: &Point, b: &Point) -> i64 {
+    let dx = (a.x() - b.x()) as i64;
+    let dy = (a.y() - b.y()) as i64;
+    return dx * dx + dy * dy;
+}
+
+// Prim's algorithm over the complete graph of points
+// return edges in the order they are added to the tree, the first index of each edge is already in the tree
+// ties are resolved by the smaller index, so the result depends only on the input order
+pub fn minimum_spanning_tree(points: &[Point]) -> Vec<(usize, usize)> {
+    let count = points.len();
+    if count <= 1 {
+        return Vec::new();
+    }
+
+    let mut in_tree: Vec<bool> = vec![false; count];
+    let mut best_distance: Vec<i64> = vec![i64::MAX; count];
+    let mut best_parent: Vec<usize> = vec![0; count];
+    let mut edges: Vec<(usize, usize)> = Vec::with_capacity(count - 1);
+
+    in_tree[0] = true;
+    for i in 1..count {
+        best_distance[i] = squared_distance(&points[0], &points[i]);
+    }
+
+    for _ in 1..count {
+        let mut next: usize = count;
+        for i in 0..count {
+            if !in_tree[i] && (next == count || best_distance[i] < best_distance[next]) {
+                next = i;
+            }
+        }
+
+        in_tree[next] = true;
+        edges.push((best_parent[next], next));
+
+        for i in 0..count {
+            if !in_tree[i] {
+                let d = squared_distance(&points[next], &points[i]);
+                if d < best_distance[i] {
+                    best_distance[i] = d;
+                    best_parent[i] = next;
+                }
+            }
+        }
+    }
+
+    return edges;
+}
+
+// edges of the Gabriel graph: points a and b are connected if there are no other points inside the circle with diameter ab
+// it is a subgraph of the Delaunay triangulation and contains the minimum spanning tree
+// edges are sorted by length, equal edges are sorted by indices
+pub fn gabriel_graph(points: &[Point]) -> Vec<(usize, usize)> {
+    let count = points.len();
+    let mut edges: Vec<(usize, usize)> = Vec::new();
+    for a in 0..count {
+        for b in (a + 1)..count {
+            let ab = squared_distance(&points[a], &points[b]);
+            let mut is_empty = true;
+            for c in 0..count {
+                if c != a && c != b && squared_distance(&points[a], &points[c]) + squared_distance(&points[b], &points[c]) < ab {
+                    is_empty = false;
+                    break;
+                }
+            }
+            if is_empty {
+                edges.push((a, b));
+            }
+        }
+    }
+
+    edges.sort_by_key(|&(a, b)| (squared_distance(&points[a], &points[b]), a, b));
+    return edges;
+}
+
+// union-find structure to track connected groups of rooms
+#[derive(Debug)]
+pub struct DisjointSet {
+    parents: Vec<usize>,
+    groups_count: usize
+}
+
+impl DisjointSet {
+    pub fn new(count: usize) -> DisjointSet {
+        return DisjointSet { parents: (0..count).collect(), groups_count: count };
+    }
+
+    pub fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        let mut current = index;
+        while self.parents[current] != root {
+            let next = self.parents[current];
+            self.parents[current] = root;
+            current = next;
+        }
+        return root;
+    }
+
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let a_root = self.find(a);
+        let b_root = self.find(b);
+        if a_root == b_root {
+            return false;
+        }
+
+        self.parents[b_root] = a_root;
+        self.groups_count -= 1;
+        return true;
+    }
+
+    pub fn is_connected(&mut self, a: usize, b: usize) -> bool {
+        return self.find(a) == self.find(b);
+    }
+
+    pub fn groups_count(&self) -> usize {
+        return self.groups_count;
+    }
+}
+
+// the shortest path between two most distant vertices, the distance is the number of edges
+// for the disconnected graph the most distant vertices are selected inside one group
+// ties are resolved by smaller indices of the start and then of the end vertex
+pub fn longest_shortest_path(count: usize, edges: &Vec<(usize, usize)>) -> Vec<usize> {
+    if count == 0 {
+        return Vec::new();
+    }
+
+    let mut adjacency: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for &(a, b) in edges {
+        adjacency[a].push(b);
+        adjacency[b].push(a);
+    }
+    for neighbours in adjacency.iter_mut() {
+        neighbours.sort();
+        neighbours.dedup();
+    }
+
+    let mut best_distance: usize = 0;
+    let mut best_path: Vec<usize> = vec![0];
+    for start in 0..count {
+        let mut distances: Vec<usize> = vec![usize::MAX; count];
+        let mut parents: Vec<usize> = (0..count).collect();
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        distances[start] = 0;
+        queue.push_back(start);
+        while let Some(current) = queue.pop_front() {
+            for &next in &adjacency[current] {
+                if distances[next] == usize::MAX {
+                    distances[next] = distances[current] + 1;
+                    parents[next] = current;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        for end in 0..count {
+            if distances[end] != usize::MAX && distances[end] > best_distance {
+                best_distance = distances[end];
+                let mut path: Vec<usize> = vec![end];
+                let mut current = end;
+                while current != start {
+                    current = parents[current];
+                    path.push(current);
+                }
+                path.reverse();
+                best_path = path;
+            }
+        }
+    }
+    return best_path;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disjoint_set_unions_groups() {
+        let mut groups = DisjointSet::new(5);
+        assert_eq!(groups.groups_count(), 5);
+        assert!(groups.union(0, 1));
+        assert!(groups.union(3, 4));
+        assert!(groups.union(1, 4));
+        assert_eq!(groups.groups_count(), 2);
+        assert!(groups.is_connected(0, 3));
+        assert!(!groups.is_connected(2, 0));
+    }
+
+    #[test]
+    fn disjoint_set_union_inside_group_is_ignored() {
+        let mut groups = DisjointSet::new(3);
+        assert!(groups.union(0, 1));
+        assert!(groups.union(1, 2));
+        assert!(!groups.union(2, 0));
+        assert!(!groups.union(1, 1));
+        assert_eq!(groups.groups_count(), 1);
+    }
+
+    #[test]
+    fn longest_shortest_path_of_chain_with_branch() {
+        // 0 - 1 - 2 - 3 with the branch 1 - 4
+        let edges: Vec<(usize, usize)> = vec![(0, 1), (1, 2), (2, 3), (1, 4)];
+        assert_eq!(longest_shortest_path(5, &edges), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn longest_shortest_path_resolves_ties_by_smaller_indices() {
+        // the cycle of four rooms, all opposite pairs have the distance 2
+        let edges: Vec<(usize, usize)> = vec![(0, 1), (1, 2), (2, 3), (3, 0)];
+        assert_eq!(longest_shortest_path(4, &edges), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn longest_shortest_path_of_disconnected_graph_stays_in_one_group() {
+        let edges: Vec<(usize, usize)> = vec![(0, 1), (2, 3), (3, 4)];
+        assert_eq!(longest_shortest_path(5, &edges), vec![2, 3, 4]);
+        assert_eq!(longest_shortest_path(3, &Vec::new()), vec![0]);
+    }
+
+    #[test]
+    fn longest_shortest_path_of_one_and_zero_rooms() {
+        assert_eq!(longest_shortest_path(1, &Vec::new()), vec![0]);
+        assert!(longest_shortest_path(0, &Vec::new()).is_empty());
+    }
+}
@@ -0,0 +1,368 @@
+use crate::level::options::RoomType;
+use super::prefab::Prefab;
+use std::fmt;
+
+// The shape of the room inside its bounding box
+// implement this trait and register the shape in the LevelGenerator to use it for rooms
+pub trait RoomShape: fmt::Debug {
+    // return the matrix with height rows and width columns, true values are floor tiles
+    fn floor_mask(&self, height: usize, width: usize) -> Vec<Vec<bool>>;
+
+    // if true, then the room height is always equal to the room width
+    fn is_square(&self) -> bool {
+        return true;
+    }
+
+    // composite rooms are randomly rotated or mirrored, and only their floor tiles (not the whole bounding box) are blocked for corridors
+    fn is_composite(&self) -> bool {
+        return false;
+    }
+
+    // the matrix of the same size as the floor mask, true values are doors
+    // if there are doors, then corridors are connected only to them, empty matrix means no doors
+    fn door_mask(&self, _height: usize, _width: usize) -> Vec<Vec<bool>> {
+        return Vec::new();
+    }
+}
+
+// the number of different orientations: 4 rotations, each of them can be mirrored
+pub const ORIENTATIONS_COUNT: usize = 8;
+
+// the mask of the shape in one of 8 orientations
+// bit 0 mirrors columns, bit 1 mirrors rows, bit 2 swaps rows and columns, so the room size is the same for any orientation
+pub fn oriented_mask(shape: &dyn RoomShape, height: usize, width: usize, orientation: usize) -> Vec<Vec<bool>> {
+    let mask = if orientation & 4 != 0 { shape.floor_mask(width, height) } else { shape.floor_mask(height, width) };
+    return orient_mask(&mask, height, width, orientation);
+}
+
+// the same for doors of the shape, empty matrix if the shape has no doors
+pub fn oriented_door_mask(shape: &dyn RoomShape, height: usize, width: usize, orientation: usize) -> Vec<Vec<bool>> {
+    let mask = if orientation & 4 != 0 { shape.door_mask(width, height) } else { shape.door_mask(height, width) };
+    if mask.is_empty() {
+        return mask;
+    }
+    return orient_mask(&mask, height, width, orientation);
+}
+
+fn orient_mask(mask: &[Vec<bool>], height: usize, width: usize, orientation: usize) -> Vec<Vec<bool>> {
+    let is_transposed = orientation & 4 != 0;
+    let mut arr: Vec<Vec<bool>> = Vec::with_capacity(height);
+    for x in 0..height {
+        let mut arr_x: Vec<bool> = Vec::with_capacity(width);
+        for y in 0..width {
+            let source_x = if orientation & 2 != 0 { height - 1 - x } else { x };
+            let source_y = if orientation & 1 != 0 { width - 1 - y } else { y };
+            let (mask_x, mask_y) = if is_transposed { (source_y, source_x) } else { (source_x, source_y) };
+            arr_x.push(mask.get(mask_x).and_then(|row| row.get(mask_y)).copied().unwrap_or(false));
+        }
+        arr.push(arr_x);
+    }
+    return arr;
+}
+
+#[derive(Debug)]
+pub struct SquareShape;
+
+impl RoomShape for SquareShape {
+    fn floor_mask(&self, height: usize, width: usize) -> Vec<Vec<bool>> {
+        return vec![vec![true; width]; height];
+    }
+}
+
+#[derive(Debug)]
+pub struct RectangleShape;
+
+impl RoomShape for RectangleShape {
+    fn floor_mask(&self, height: usize, width: usize) -> Vec<Vec<bool>> {
+        return vec![vec![true; width]; height];
+    }
+
+    fn is_square(&self) -> bool {
+        return false;
+    }
+}
+
+#[derive(Debug)]
+pub struct CrossShape;
+
+impl RoomShape for CrossShape {
+    fn floor_mask(&self, height: usize, width: usize) -> Vec<Vec<bool>> {
+        let mut arr: Vec<Vec<bool>> = Vec::with_capacity(height);
+
+        let height_float = height as f64;
+        let width_float = width as f64;
+        for x in 0..height {
+            let mut arr_x: Vec<bool> = Vec::with_capacity(width);
+            let x_float = x as f64;
+            for y in 0..width {
+                let y_float = y as f64;
+                let x_lower_bound = height_float * 0.333 - 1.0;
+                let x_upper_bound = height_float * 0.666;
+
+                let y_lower_bound = width_float * 0.333 - 1.0;
+                let y_upper_bound = width_float * 0.666;
+                arr_x.push((x_float > x_lower_bound && x_float < x_upper_bound) || (y_float > y_lower_bound && y_float < y_upper_bound));
+            }
+            arr.push(arr_x);
+        }
+        return arr;
+    }
+}
+
+#[derive(Debug)]
+pub struct DiamondShape;
+
+impl RoomShape for DiamondShape {
+    fn floor_mask(&self, height: usize, width: usize) -> Vec<Vec<bool>> {
+        let mut offset: i32 = 0;
+        let y_middle: i32 = (width / 2) as i32;
+
+        let mut arr: Vec<Vec<bool>> = Vec::with_capacity(height);
+        for x in 0..height {
+            let mut arr_x: Vec<bool> = Vec::with_capacity(width);
+            for y in 0..width {
+                let y_int = y as i32;
+                arr_x.push(!(y_int < y_middle - offset || y_int > y_middle + offset));
+            }
+            offset = if x < height / 2 { offset + 1 } else { offset - 1 };
+            arr.push(arr_x);
+        }
+        return arr;
+    }
+}
+
+// floor tiles inside the ellipse, which touches the borders of the room
+// tiles closer to the center than inner_radius (relative to the ellipse size) are solid
+fn ellipse_mask(height: usize, width: usize, inner_radius: f64) -> Vec<Vec<bool>> {
+    let x_radius = height as f64 / 2.0;
+    let y_radius = width as f64 / 2.0;
+    let mut arr: Vec<Vec<bool>> = Vec::with_capacity(height);
+    for x in 0..height {
+        let mut arr_x: Vec<bool> = Vec::with_capacity(width);
+        let dx = (x as f64 + 0.5 - x_radius) / x_radius;
+        for y in 0..width {
+            let dy = (y as f64 + 0.5 - y_radius) / y_radius;
+            let distance = dx * dx + dy * dy;
+            arr_x.push(distance <= 1.0 && distance >= inner_radius * inner_radius);
+        }
+        arr.push(arr_x);
+    }
+    return arr;
+}
+
+#[derive(Debug)]
+pub struct CircleShape;
+
+impl RoomShape for CircleShape {
+    fn floor_mask(&self, height: usize, width: usize) -> Vec<Vec<bool>> {
+        return ellipse_mask(height, width, 0.0);
+    }
+}
+
+#[derive(Debug)]
+pub struct EllipseShape;
+
+impl RoomShape for EllipseShape {
+    fn floor_mask(&self, height: usize, width: usize) -> Vec<Vec<bool>> {
+        return ellipse_mask(height, width, 0.0);
+    }
+
+    fn is_square(&self) -> bool {
+        return false;
+    }
+}
+
+// the circle with the solid core
+#[derive(Debug)]
+pub struct RingShape;
+
+impl RoomShape for RingShape {
+    fn floor_mask(&self, height: usize, width: usize) -> Vec<Vec<bool>> {
+        return ellipse_mask(height, width, 0.5);
+    }
+}
+
+// fill the rectangle of the mask, the end values are excluded
+fn fill_rectangle(mask: &mut [Vec<bool>], x_start: usize, x_end: usize, y_start: usize, y_end: usize) {
+    for x in x_start..x_end {
+        for y in y_start..y_end {
+            mask[x][y] = true;
+        }
+    }
+}
+
+// the thickness of the arm of the composite room, at least one tile
+fn arm_size(dimension: usize, parts: usize) -> usize {
+    return (dimension / parts).max(1);
+}
+
+// vertical arm along the left side and horizontal arm along the bottom side
+#[derive(Debug)]
+pub struct LShape;
+
+impl RoomShape for LShape {
+    fn floor_mask(&self, height: usize, width: usize) -> Vec<Vec<bool>> {
+        let mut arr = vec![vec![false; width]; height];
+        let x_arm = arm_size(height + 1, 2);
+        let y_arm = arm_size(width + 1, 2);
+        fill_rectangle(&mut arr, 0, height, 0, y_arm);
+        fill_rectangle(&mut arr, height - x_arm, height, 0, width);
+        return arr;
+    }
+
+    fn is_square(&self) -> bool {
+        return false;
+    }
+
+    fn is_composite(&self) -> bool {
+        return true;
+    }
+}
+
+// horizontal bar at the top and the stem in the middle
+#[derive(Debug)]
+pub struct TShape;
+
+impl RoomShape for TShape {
+    fn floor_mask(&self, height: usize, width: usize) -> Vec<Vec<bool>> {
+        let mut arr = vec![vec![false; width]; height];
+        let bar = arm_size(height, 2);
+        let stem = arm_size(width, 3) | 1;
+        let stem_start = width.saturating_sub(stem) / 2;
+        fill_rectangle(&mut arr, 0, bar, 0, width);
+        fill_rectangle(&mut arr, 0, height, stem_start, (stem_start + stem).min(width));
+        return arr;
+    }
+
+    fn is_square(&self) -> bool {
+        return false;
+    }
+
+    fn is_composite(&self) -> bool {
+        return true;
+    }
+}
+
+// two vertical arms, connected at the bottom
+#[derive(Debug)]
+pub struct UShape;
+
+impl RoomShape for UShape {
+    fn floor_mask(&self, height: usize, width: usize) -> Vec<Vec<bool>> {
+        let mut arr = vec![vec![false; width]; height];
+        let bar = arm_size(height, 3);
+        let arm = arm_size(width, 3);
+        fill_rectangle(&mut arr, 0, height, 0, arm);
+        fill_rectangle(&mut arr, 0, height, width - arm, width);
+        fill_rectangle(&mut arr, height - bar, height, 0, width);
+        return arr;
+    }
+
+    fn is_square(&self) -> bool {
+        return false;
+    }
+
+    fn is_composite(&self) -> bool {
+        return true;
+    }
+}
+
+// the plus, where arms cross closer to the top left corner
+#[derive(Debug)]
+pub struct OffsetPlusShape;
+
+impl RoomShape for OffsetPlusShape {
+    fn floor_mask(&self, height: usize, width: usize) -> Vec<Vec<bool>> {
+        let mut arr = vec![vec![false; width]; height];
+        let x_arm = arm_size(height, 3);
+        let y_arm = arm_size(width, 3);
+        let x_start = height / 4;
+        let y_start = width / 4;
+        fill_rectangle(&mut arr, x_start, x_start + x_arm, 0, width);
+        fill_rectangle(&mut arr, 0, height, y_start, y_start + y_arm);
+        return arr;
+    }
+
+    fn is_square(&self) -> bool {
+        return false;
+    }
+
+    fn is_composite(&self) -> bool {
+        return true;
+    }
+}
+
+static SQUARE_SHAPE: SquareShape = SquareShape;
+static RECTANGLE_SHAPE: RectangleShape = RectangleShape;
+static CROSS_SHAPE: CrossShape = CrossShape;
+static DIAMOND_SHAPE: DiamondShape = DiamondShape;
+static CIRCLE_SHAPE: CircleShape = CircleShape;
+static ELLIPSE_SHAPE: EllipseShape = EllipseShape;
+static RING_SHAPE: RingShape = RingShape;
+static L_SHAPE: LShape = LShape;
+static T_SHAPE: TShape = TShape;
+static U_SHAPE: UShape = UShape;
+static OFFSET_PLUS_SHAPE: OffsetPlusShape = OffsetPlusShape;
+
+// built-in shapes and shapes, registered by the user
+#[derive(Debug)]
+pub struct RoomShapes {
+    custom: Vec<Box<dyn RoomShape>>,
+    prefabs: Vec<Prefab>
+}
+
+impl Default for RoomShapes {
+    fn default() -> Self {
+        return Self::new();
+    }
+}
+
+impl RoomShapes {
+    pub fn new() -> RoomShapes {
+        return RoomShapes { custom: Vec::new(), prefabs: Vec::new() };
+    }
+
+    // return the room type for the new prefab
+    pub fn register_prefab(&mut self, prefab: Prefab) -> RoomType {
+        self.prefabs.push(prefab);
+        return RoomType::Prefab(self.prefabs.len() - 1);
+    }
+
+    pub fn prefab(&self, index: usize) -> Option<&Prefab> {
+        return self.prefabs.get(index);
+    }
+
+    // return the room type for the new shape
+    pub fn register(&mut self, shape: Box<dyn RoomShape>) -> RoomType {
+        self.custom.push(shape);
+        return RoomType::Custom(self.custom.len() - 1);
+    }
+
+    pub fn contains(&self, room_type: RoomType) -> bool {
+        return match room_type {
+            RoomType::Custom(index) => index < self.custom.len(),
+            RoomType::Prefab(index) => index < self.prefabs.len(),
+            _ => true
+        };
+    }
+
+    pub fn get(&self, room_type: RoomType) -> &dyn RoomShape {
+        return match room_type {
+            RoomType::Square => &SQUARE_SHAPE,
+            RoomType::Rectangle => &RECTANGLE_SHAPE,
+            RoomType::Cross => &CROSS_SHAPE,
+            RoomType::Diamond => &DIAMOND_SHAPE,
+            RoomType::Circle => &CIRCLE_SHAPE,
+            RoomType::Ellipse => &ELLIPSE_SHAPE,
+            RoomType::Ring => &RING_SHAPE,
+            RoomType::LShape => &L_SHAPE,
+            RoomType::TShape => &T_SHAPE,
+            RoomType::UShape => &U_SHAPE,
+            RoomType::OffsetPlus => &OFFSET_PLUS_SHAPE,
+            RoomType::Custom(index) => self.custom[index].as_ref(),
+            RoomType::Prefab(index) => &self.prefabs[index],
+            // the floor of the cavern is grown by the generator, the shape is used only for the bounding box
+            RoomType::Cavern => &RECTANGLE_SHAPE
+        };
+    }
+}
@@ -0,0 +1,115 @@
+use super::preudo_random::PseudoRandom;
+use super::room_generator::Room;
+use super::cave_generator::separate_diagonal_floor;
+use crate::level::options::{Options, RoomType};
+
+const DIRECTIONS: [(i32, i32); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+struct Walker {
+    x: usize,
+    y: usize,
+    direction: usize,
+    steps_left: usize
+}
+
+struct Tunnels {
+    floor: Vec<Vec<bool>>,
+    // all floor tiles in the order of carving, new walkers start from one of them
+    floor_tiles: Vec<(usize, usize)>
+}
+
+impl Tunnels {
+    fn carve(&mut self, x: usize, y: usize) {
+        if !self.floor[x][y] {
+            self.floor[x][y] = true;
+            self.floor_tiles.push((x, y));
+        }
+    }
+}
+
+// floor mask of tunnels and rooms at turning points of walkers, x is the row, y is the column
+// walkers move inside the level border, when the walker reaches the border, it turns
+// each finished walker is replaced by the new one from the random floor tile, so all tunnels are connected
+pub fn generate_tunnels(random: &mut PseudoRandom, options: &Options) -> (Vec<Vec<bool>>, Vec<Room>) {
+    let settings = options.tunnel_settings();
+    let height = options.level_height();
+    let width = options.level_width();
+    let border = options.border();
+    let mut tunnels = Tunnels { floor: vec![vec![false; width]; height], floor_tiles: Vec::new() };
+    let mut rooms: Vec<Room> = Vec::new();
+    if height <= 2 * border || width <= 2 * border {
+        return (tunnels.floor, rooms);
+    }
+
+    let inner_height = height - 2 * border;
+    let inner_width = width - 2 * border;
+    let target = ((inner_height * inner_width) as f64 * settings.coverage()).ceil().max(1.0) as usize;
+    let start = (height / 2, width / 2);
+    tunnels.carve(start.0, start.1);
+
+    let mut walkers: Vec<Walker> = Vec::with_capacity(settings.walkers());
+    for _ in 0..settings.walkers() {
+        walkers.push(Walker { x: start.0, y: start.1, direction: random.next(0, DIRECTIONS.len() - 1), steps_left: settings.lifetime() });
+    }
+
+    // the limit of steps for the case, when the coverage can not be reached
+    let mut steps = 100 * inner_height * inner_width;
+    while tunnels.floor_tiles.len() < target && steps > 0 {
+        for walker in walkers.iter_mut() {
+            steps = steps.saturating_sub(1);
+            let mut is_turn = random.next_float() < settings.turn_probability();
+            if is_turn {
+                // turn to the left or to the right
+                walker.direction = (walker.direction + 1 + 2 * random.next(0, 1)) % DIRECTIONS.len();
+            }
+            let (dx, dy) = DIRECTIONS[walker.direction];
+            let next_x = walker.x as i32 + dx;
+            let next_y = walker.y as i32 + dy;
+            if next_x < border as i32 || next_y < border as i32 || next_x >= (height - border) as i32 || next_y >= (width - border) as i32 {
+                walker.direction = (walker.direction + 1 + 2 * random.next(0, 1)) % DIRECTIONS.len();
+                is_turn = true;
+            }
+            else {
+                walker.x = next_x as usize;
+                walker.y = next_y as usize;
+                tunnels.carve(walker.x, walker.y);
+            }
+
+            if is_turn && random.next_float() < settings.room_probability() {
+                match room_at_point(random, options, walker.x, walker.y) {
+                    Some(room) if !rooms.iter().any(|r| r.intersects(&room, options.room_border())) => {
+                        for x in 0..room.height() {
+                            for y in 0..room.width() {
+                                tunnels.carve(room.position().x() as usize + x, room.position().y() as usize + y);
+                            }
+                        }
+                        rooms.push(room);
+                    },
+                    _ => {}
+                };
+            }
+
+            walker.steps_left -= 1;
+            if walker.steps_left == 0 {
+                let (x, y) = tunnels.floor_tiles[random.next(0, tunnels.floor_tiles.len() - 1)];
+                *walker = Walker { x, y, direction: random.next(0, DIRECTIONS.len() - 1), steps_left: settings.lifetime() };
+            }
+        }
+    }
+
+    let mut floor = tunnels.floor;
+    separate_diagonal_floor(&mut floor);
+    return (floor, rooms);
+}
+
+// the rectangle room with the center at the point, moved inside the level border
+fn room_at_point(random: &mut PseudoRandom, options: &Options, x: usize, y: usize) -> Option<Room> {
+    let room_width = random.next_odd(options.min_room_width(), options.max_room_width());
+    let room_height = random.next_odd(options.min_room_height(), options.max_room_height());
+    if room_height + 2 * options.border() > options.level_height() || room_width + 2 * options.border() > options.level_width() {
+        return None;
+    }
+    let room_x = x.saturating_sub(room_height / 2).clamp(options.border(), options.level_height() - options.border() - room_height);
+    let room_y = y.saturating_sub(room_width / 2).clamp(options.border(), options.level_width() - options.border() - room_width);
+    return Some(Room::new(room_height, room_width, room_x, room_y, RoomType::Rectangle));
+}
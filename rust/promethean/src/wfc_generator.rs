@@ -0,0 +1,306 @@
+use super::preudo_random::PseudoRandom;
+use super::cave_generator::separate_diagonal_floor;
+use crate::level::Level;
+use crate::level::options::{OptionsError, WfcSettings};
+use std::collections::HashMap;
+
+// the floor tile of the ascii sample, all other characters are walls
+pub const WFC_SAMPLE_FLOOR: char = '.';
+
+// offsets to the neighbour cell, the opposite direction of d is (d + 2) % 4
+const DIRECTIONS: [(i32, i32); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+// the floor mask, from which the generator learns patterns
+#[derive(Debug, Clone)]
+pub struct WfcSample {
+    floor: Vec<Vec<bool>>
+}
+
+impl WfcSample {
+    // rows are separated by new lines, empty lines at the start and at the end are ignored, shorter rows are filled by walls
+    pub fn parse(sample: &str) -> Result<WfcSample, OptionsError> {
+        let lines: Vec<&str> = sample.lines().map(|l| l.trim_end_matches('\r')).collect();
+        let start = lines.iter().position(|l| !l.trim().is_empty()).unwrap_or(lines.len());
+        let end = lines.iter().rposition(|l| !l.trim().is_empty()).map(|i| i + 1).unwrap_or(start);
+        let rows = &lines[start..end];
+        let width = rows.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        if width == 0 {
+            return Err(OptionsError::EmptyWfcSample);
+        }
+
+        let floor: Vec<Vec<bool>> = rows.iter().map(|l| {
+            let mut row: Vec<bool> = l.chars().map(|c| c == WFC_SAMPLE_FLOOR).collect();
+            row.resize(width, false);
+            return row;
+        }).collect();
+        return Ok(WfcSample { floor });
+    }
+
+    // generated levels are inflated twice, so only each second tile is used
+    pub fn from_level(level: &Level) -> WfcSample {
+        let floor: Vec<Vec<bool>> = (0..level.height() / 2).map(|x| {
+            return (0..level.width() / 2).map(|y| level.get_from_coordinates(2 * x, 2 * y).is_walkable()).collect();
+        }).collect();
+        return WfcSample { floor };
+    }
+
+    pub fn height(&self) -> usize {
+        return self.floor.len();
+    }
+
+    pub fn width(&self) -> usize {
+        return if !self.floor.is_empty() { self.floor[0].len() } else { 0 };
+    }
+}
+
+// square part of the sample, stored row by row
+type Pattern = Vec<bool>;
+
+fn transform(pattern: &Pattern, size: usize, f: impl Fn(usize, usize) -> (usize, usize)) -> Pattern {
+    let mut to_return: Pattern = vec![false; size * size];
+    for x in 0..size {
+        for y in 0..size {
+            let (sx, sy) = f(x, y);
+            to_return[x * size + y] = pattern[sx * size + sy];
+        }
+    }
+    return to_return;
+}
+
+// all different patterns of the sample and their frequencies
+fn extract_patterns(sample: &WfcSample, settings: &WfcSettings) -> (Vec<Pattern>, Vec<f64>) {
+    let size = settings.pattern_size();
+    let mut patterns: Vec<Pattern> = Vec::new();
+    let mut weights: Vec<f64> = Vec::new();
+    let mut indices: HashMap<Pattern, usize> = HashMap::new();
+    for x in 0..=(sample.height() - size) {
+        for y in 0..=(sample.width() - size) {
+            let pattern: Pattern = (0..size * size).map(|i| sample.floor[x + i / size][y + i % size]).collect();
+            let mut variants: Vec<Pattern> = vec![pattern];
+            if settings.symmetry() {
+                // three rotations and the mirror of each of four rotations
+                for i in 0..3 {
+                    let rotated = transform(&variants[i], size, |a, b| (size - 1 - b, a));
+                    variants.push(rotated);
+                }
+                for i in 0..4 {
+                    let mirrored = transform(&variants[i], size, |a, b| (a, size - 1 - b));
+                    variants.push(mirrored);
+                }
+            }
+            for variant in variants {
+                match indices.get(&variant) {
+                    Some(&index) => weights[index] += 1.0,
+                    None => {
+                        indices.insert(variant.clone(), patterns.len());
+                        patterns.push(variant);
+                        weights.push(1.0);
+                    }
+                };
+            }
+        }
+    }
+    return (patterns, weights);
+}
+
+// can the second pattern be placed with the offset from the first one
+fn agrees(first: &Pattern, second: &Pattern, size: usize, dx: i32, dy: i32) -> bool {
+    let n = size as i32;
+    for x in dx.max(0)..(n + dx).min(n) {
+        for y in dy.max(0)..(n + dy).min(n) {
+            if first[(x * n + y) as usize] != second[((x - dx) * n + (y - dy)) as usize] {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+struct Wave {
+    height: usize,
+    width: usize,
+    // possible[cell][pattern]
+    possible: Vec<Vec<bool>>,
+    // compatible[cell][pattern][direction] is the number of patterns in the neighbour cell in the opposite direction, which allow the pattern
+    compatible: Vec<Vec<[usize; 4]>>,
+    counts: Vec<usize>,
+    sums_of_weights: Vec<f64>,
+    sums_of_weight_logs: Vec<f64>,
+    stack: Vec<(usize, usize)>
+}
+
+impl Wave {
+    fn new(height: usize, width: usize, weights: &[f64], propagator: &[[Vec<usize>; 4]]) -> Wave {
+        let cells = height * width;
+        let sum: f64 = weights.iter().sum();
+        let sum_logs: f64 = weights.iter().map(|w| w * w.ln()).sum();
+        let initial: Vec<[usize; 4]> = (0..weights.len()).map(|p| {
+            return [0, 1, 2, 3].map(|d| propagator[p][(d + 2) % 4].len());
+        }).collect();
+        return Wave {
+            height,
+            width,
+            possible: vec![vec![true; weights.len()]; cells],
+            compatible: vec![initial; cells],
+            counts: vec![weights.len(); cells],
+            sums_of_weights: vec![sum; cells],
+            sums_of_weight_logs: vec![sum_logs; cells],
+            stack: Vec::new()
+        };
+    }
+
+    fn ban(&mut self, cell: usize, pattern: usize, weights: &[f64]) {
+        self.possible[cell][pattern] = false;
+        self.compatible[cell][pattern] = [0; 4];
+        self.counts[cell] -= 1;
+        self.sums_of_weights[cell] -= weights[pattern];
+        self.sums_of_weight_logs[cell] -= weights[pattern] * weights[pattern].ln();
+        self.stack.push((cell, pattern));
+    }
+
+    // return false on contradiction
+    fn propagate(&mut self, weights: &[f64], propagator: &[[Vec<usize>; 4]]) -> bool {
+        while let Some((cell, pattern)) = self.stack.pop() {
+            let x = (cell / self.width) as i32;
+            let y = (cell % self.width) as i32;
+            for d in 0..4 {
+                let nx = x + DIRECTIONS[d].0;
+                let ny = y + DIRECTIONS[d].1;
+                if nx < 0 || ny < 0 || nx >= self.height as i32 || ny >= self.width as i32 {
+                    continue;
+                }
+                let neighbour = nx as usize * self.width + ny as usize;
+                for &other in &propagator[pattern][d] {
+                    if !self.possible[neighbour][other] {
+                        continue;
+                    }
+                    self.compatible[neighbour][other][d] -= 1;
+                    if self.compatible[neighbour][other][d] == 0 {
+                        self.ban(neighbour, other, weights);
+                        if self.counts[neighbour] == 0 {
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+        return true;
+    }
+
+    // the undecided cell with the smallest entropy, None if all cells are decided
+    fn min_entropy_cell(&self, random: &mut PseudoRandom) -> Option<usize> {
+        let mut to_return: Option<usize> = None;
+        let mut min_entropy = f64::MAX;
+        for cell in 0..self.counts.len() {
+            if self.counts[cell] <= 1 {
+                continue;
+            }
+            let sum = self.sums_of_weights[cell];
+            // small noise selects the random cell from cells with the same entropy
+            let entropy = sum.ln() - self.sums_of_weight_logs[cell] / sum + 1e-6 * random.next_float();
+            if entropy < min_entropy {
+                min_entropy = entropy;
+                to_return = Some(cell);
+            }
+        }
+        return to_return;
+    }
+}
+
+// one attempt of the wave function collapse, None on contradiction
+fn run(random: &mut PseudoRandom, height: usize, width: usize, weights: &[f64], propagator: &[[Vec<usize>; 4]]) -> Option<Vec<usize>> {
+    let mut wave = Wave::new(height, width, weights, propagator);
+    // patterns from the edge of the sample can have no neighbours in some directions, so they can be only at the edge of the wave
+    for cell in 0..height * width {
+        let x = (cell / width) as i32;
+        let y = (cell % width) as i32;
+        for d in 0..4 {
+            let (dx, dy) = DIRECTIONS[(d + 2) % 4];
+            if x + dx < 0 || y + dy < 0 || x + dx >= height as i32 || y + dy >= width as i32 {
+                continue;
+            }
+            for p in 0..weights.len() {
+                if wave.possible[cell][p] && wave.compatible[cell][p][d] == 0 {
+                    wave.ban(cell, p, weights);
+                }
+            }
+        }
+        if wave.counts[cell] == 0 {
+            return None;
+        }
+    }
+    if !wave.propagate(weights, propagator) {
+        return None;
+    }
+
+    while let Some(cell) = wave.min_entropy_cell(random) {
+        // select the pattern by weights
+        let mut value = random.next_float() * wave.sums_of_weights[cell];
+        let mut selected = usize::MAX;
+        for p in 0..weights.len() {
+            if wave.possible[cell][p] {
+                selected = p;
+                if value < weights[p] {
+                    break;
+                }
+                value -= weights[p];
+            }
+        }
+        for p in 0..weights.len() {
+            if p != selected && wave.possible[cell][p] {
+                wave.ban(cell, p, weights);
+            }
+        }
+        if !wave.propagate(weights, propagator) {
+            return None;
+        }
+    }
+    return Some((0..height * width).map(|cell| wave.possible[cell].iter().position(|&p| p).unwrap_or(0)).collect());
+}
+
+// floor mask of the level, synthesized from patterns of the sample, and the number of restarts after contradictions
+// the level border is always a wall, patterns are not wrapped around edges of the sample or the level
+pub fn generate_wfc(random: &mut PseudoRandom, height: usize, width: usize, border: usize, sample: &WfcSample, settings: &WfcSettings) -> Result<(Vec<Vec<bool>>, usize), OptionsError> {
+    let size = settings.pattern_size();
+    if sample.height() < size || sample.width() < size {
+        return Err(OptionsError::WfcSampleTooSmall { pattern_size: size, sample_height: sample.height(), sample_width: sample.width() });
+    }
+    let inner_height = height.saturating_sub(2 * border);
+    let inner_width = width.saturating_sub(2 * border);
+    if inner_height < size || inner_width < size {
+        return Err(OptionsError::WfcLevelTooSmall { pattern_size: size, level_height: height, level_width: width, border });
+    }
+
+    let (patterns, weights) = extract_patterns(sample, settings);
+    let propagator: Vec<[Vec<usize>; 4]> = (0..patterns.len()).map(|p| {
+        return [0, 1, 2, 3].map(|d| (0..patterns.len()).filter(|&q| agrees(&patterns[p], &patterns[q], size, DIRECTIONS[d].0, DIRECTIONS[d].1)).collect());
+    }).collect();
+
+    let wave_height = inner_height - size + 1;
+    let wave_width = inner_width - size + 1;
+    let mut restarts: usize = 0;
+    loop {
+        match run(random, wave_height, wave_width, &weights, &propagator) {
+            Some(result) => {
+                let mut floor: Vec<Vec<bool>> = vec![vec![false; width]; height];
+                for x in 0..inner_height {
+                    for y in 0..inner_width {
+                        // the last cells of the wave define the rest of their patterns
+                        let cell_x = x.min(wave_height - 1);
+                        let cell_y = y.min(wave_width - 1);
+                        let pattern = &patterns[result[cell_x * wave_width + cell_y]];
+                        floor[border + x][border + y] = pattern[(x - cell_x) * size + (y - cell_y)];
+                    }
+                }
+                separate_diagonal_floor(&mut floor);
+                return Ok((floor, restarts));
+            },
+            None => {
+                if restarts >= settings.max_restarts() {
+                    return Err(OptionsError::WfcContradiction { restarts });
+                }
+                restarts += 1;
+            }
+        };
+    }
+}
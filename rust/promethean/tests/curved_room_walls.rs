@@ -0,0 +1,37 @@
+use promethean_wasm::level::Level;
+use promethean_wasm::level::options::{OptionsBuilder, RoomType, Tile};
+use promethean_wasm::level_generator::LevelGenerator;
+
+// walkable tiles, which touch empty tiles by the side or by the corner, are holes in walls
+fn holes_count(level: &Level) -> usize {
+    let mut count: usize = 0;
+    for (x, y, tile) in level.iter() {
+        if !tile.is_walkable() {
+            continue;
+        }
+        for nx in x.saturating_sub(1)..(x + 2).min(level.height()) {
+            for ny in y.saturating_sub(1)..(y + 2).min(level.width()) {
+                if level.get_from_coordinates(nx, ny) == Tile::Empty {
+                    count += 1;
+                }
+            }
+        }
+    }
+    return count;
+}
+
+// outlines of curved rooms have diagonal steps, grid patterns should close them by walls
+#[test]
+fn curved_rooms_have_closed_walls() {
+    for room_type in [RoomType::Circle, RoomType::Ellipse, RoomType::Ring] {
+        for seed in 1..=10 {
+            let options = OptionsBuilder::new()
+                .random_seed(seed)
+                .room_types(vec![room_type])
+                .build()
+                .unwrap();
+            let level = LevelGenerator::new_with_options(options).generate().unwrap();
+            assert_eq!(holes_count(&level), 0, "seed {} with {:?}", seed, room_type);
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use promethean_wasm::level::options::OptionsBuilder;
+use promethean_wasm::level_generator::LevelGenerator;
+use promethean_wasm::level_generator::dungeon_generator::{Dungeon, DungeonGenerator};
+
+// floors reseed the level generator, the next call should start from the dungeon seed again
+#[test]
+fn repeated_generation_gives_the_same_dungeon() {
+    let options = OptionsBuilder::new().random_seed(7).build().unwrap();
+    let mut generator = DungeonGenerator::new(LevelGenerator::new_with_options(options), 3);
+    let first = generator.generate().unwrap();
+    let second = generator.generate().unwrap();
+    assert_eq!(first.seeds_inner(), second.seeds_inner());
+    let stairs = |d: &Dungeon| -> Vec<(i32, i32)> { d.stairs_inner().iter().map(|p| (p.x(), p.y())).collect() };
+    assert_eq!(stairs(&first), stairs(&second));
+    for (a, b) in first.floors_inner().iter().zip(second.floors_inner()) {
+        assert_eq!(a.render_inner(), b.render_inner());
+    }
+}
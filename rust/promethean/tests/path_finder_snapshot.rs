@@ -0,0 +1,47 @@
+use promethean_wasm::level::Level;
+use promethean_wasm::level::options::{ConnectionStrategy, OptionsBuilder, RetryPolicy};
+use promethean_wasm::level_generator::LevelGenerator;
+
+// hashes of levels from the path finder with the linear open set
+// the binary heap path finder should find the same corridors, so levels should not change
+// retries and the placement budget are disabled, because they are added after these levels
+const SNAPSHOTS: [(usize, ConnectionStrategy, u64); 12] = [
+    (1, ConnectionStrategy::MinimumSpanningTree, 0xb0b5c1f409ced3d3),
+    (1, ConnectionStrategy::Chain, 0x031e95b518948b95),
+    (2, ConnectionStrategy::MinimumSpanningTree, 0x3bdf88f0498ff98f),
+    (2, ConnectionStrategy::Chain, 0x833fdcc48d017fdd),
+    (3, ConnectionStrategy::MinimumSpanningTree, 0x014d73a1f82ce8cf),
+    (3, ConnectionStrategy::Chain, 0x81b47b0767e8a5bf),
+    (7, ConnectionStrategy::MinimumSpanningTree, 0x538c25813ac34a37),
+    (7, ConnectionStrategy::Chain, 0x46ec15e2549268cd),
+    (42, ConnectionStrategy::MinimumSpanningTree, 0x3f4201d1998c7355),
+    (42, ConnectionStrategy::Chain, 0x8df8e9cd8dde573d),
+    (99, ConnectionStrategy::MinimumSpanningTree, 0x9d5c6ac666ff26b5),
+    (99, ConnectionStrategy::Chain, 0x37f2effa6818972f)
+];
+
+// FNV-1a hash of tile values row by row
+fn tiles_hash(level: &Level) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &tile in level.render_inner() {
+        hash ^= tile as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    return hash;
+}
+
+#[test]
+fn levels_match_snapshots() {
+    for (seed, connection_strategy, expected) in SNAPSHOTS {
+        let options = OptionsBuilder::new()
+            .random_seed(seed)
+            .connection_strategy(connection_strategy)
+            .loop_ratio(0.3)
+            .retry_policy(RetryPolicy::new(0, 0, false))
+            .placement_attempts(0)
+            .build()
+            .unwrap();
+        let level = LevelGenerator::new_with_options(options).generate().unwrap();
+        assert_eq!(tiles_hash(&level), expected, "seed {} with {:?}", seed, connection_strategy);
+    }
+}
@@ -0,0 +1,25 @@
+use promethean_wasm::level::options::{OptionsBuilder, RoomPlacement};
+use promethean_wasm::level_generator::LevelGenerator;
+
+// centers of the output level are inflated, so the distance is compared in tiles of the output level
+#[test]
+fn room_centers_keep_min_distance() {
+    let min_distance: usize = 12;
+    for seed in 1..=10 {
+        let options = OptionsBuilder::new()
+            .random_seed(seed)
+            .room_placement(RoomPlacement::Poisson)
+            .min_room_distance(min_distance)
+            .build()
+            .unwrap();
+        let level = LevelGenerator::new_with_options(options).generate().unwrap();
+        let centers = level.statistics_inner().room_centers_inner();
+        for i in 0..centers.len() {
+            for j in (i + 1)..centers.len() {
+                let dx = (centers[i].x() - centers[j].x()) as i64;
+                let dy = (centers[i].y() - centers[j].y()) as i64;
+                assert!(dx * dx + dy * dy >= (4 * min_distance * min_distance) as i64, "seed {} rooms {} and {}", seed, i, j);
+            }
+        }
+    }
+}
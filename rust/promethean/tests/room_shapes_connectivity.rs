@@ -0,0 +1,32 @@
+use promethean_wasm::level::options::{OptionsBuilder, RoomType};
+use promethean_wasm::level_generator::LevelGenerator;
+
+const ROOM_TYPES: [RoomType; 11] = [
+    RoomType::Square,
+    RoomType::Rectangle,
+    RoomType::Cross,
+    RoomType::Diamond,
+    RoomType::Circle,
+    RoomType::Ellipse,
+    RoomType::Ring,
+    RoomType::LShape,
+    RoomType::TShape,
+    RoomType::UShape,
+    RoomType::OffsetPlus
+];
+
+// each built-in shape alone should give levels with all rooms connected
+#[test]
+fn built_in_shapes_connect_all_rooms() {
+    for room_type in ROOM_TYPES {
+        for seed in 1..=20 {
+            let options = OptionsBuilder::new()
+                .random_seed(seed)
+                .room_types(vec![room_type])
+                .build()
+                .unwrap();
+            let level = LevelGenerator::new_with_options(options).generate().unwrap();
+            assert!(level.statistics_inner().all_corridors(), "seed {} with {:?}", seed, room_type);
+        }
+    }
+}